@@ -0,0 +1,454 @@
+use crate::db::Db;
+use crate::paths::workspaces_dir;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+use tauri::State;
+
+use super::download::safe_relative;
+use super::now_ms;
+use super::progress::save_route;
+use super::source::{parse_source_url, source_id};
+use super::types::{MergeStrategy, ProgressImport, Route, UnmatchedCourse, UnmatchedStep};
+
+const PROGRESS_FORMAT: u32 = 1;
+
+/// Learner state for every course with any, keyed by source URL so it
+/// can be matched on a machine where course ids differ.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressFile {
+    format: u32,
+    exported_at: i64,
+    app_version: String,
+    courses: Vec<CourseProgress>,
+    #[serde(default)]
+    route: Option<SavedRoute>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CourseProgress {
+    source_url: String,
+    title: String,
+    #[serde(default)]
+    completed: Vec<CompletedStep>,
+    #[serde(default)]
+    positions: Vec<StepPosition>,
+    #[serde(default)]
+    slides: Vec<CompletedSlide>,
+    /// Folder names of lab workspaces that already ran their setup.
+    #[serde(default)]
+    labs: Vec<ProvisionedLab>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletedStep {
+    step_id: String,
+    completed_at: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StepPosition {
+    step_id: String,
+    slide_index: i64,
+    #[serde(default)]
+    slide_count: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletedSlide {
+    step_id: String,
+    slide_id: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProvisionedLab {
+    slug: String,
+    provisioned_at: i64,
+}
+
+/// `Route` with the course named by source URL instead of local id.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+enum SavedRoute {
+    Browser,
+    #[serde(rename_all = "camelCase")]
+    Course {
+        source_url: String,
+        step_index: i64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Editor {
+        source_url: String,
+    },
+}
+
+fn query_rows<T>(
+    conn: &Connection,
+    sql: &str,
+    id: &str,
+    map: impl FnMut(&rusqlite::Row) -> rusqlite::Result<T>,
+) -> Result<Vec<T>, String> {
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| format!("Failed to query progress: {e}"))?;
+    stmt.query_map(params![id], map)
+        .map_err(|e| format!("Failed to read progress: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read progress: {e}"))
+}
+
+/// Lab workspaces live at `{workspaces}/{course id}/{slug}`.
+fn provisioned_labs(conn: &Connection, id: &str) -> Result<Vec<ProvisionedLab>, String> {
+    let course_dir = workspaces_dir().join(id);
+    let mut stmt = conn
+        .prepare("SELECT workspace_path, provisioned_at FROM lab_provision ORDER BY workspace_path")
+        .map_err(|e| format!("Failed to query labs: {e}"))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(|e| format!("Failed to read labs: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read labs: {e}"))?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(path, provisioned_at)| {
+            let slug = Path::new(&path).strip_prefix(&course_dir).ok()?;
+            Some(ProvisionedLab {
+                slug: slug.to_str()?.to_string(),
+                provisioned_at,
+            })
+        })
+        .collect())
+}
+
+fn course_progress(
+    conn: &Connection,
+    id: &str,
+    source_url: String,
+    title: String,
+) -> Result<CourseProgress, String> {
+    Ok(CourseProgress {
+        completed: query_rows(
+            conn,
+            "SELECT step_id, completed_at FROM step_completion WHERE course_id = ?1 ORDER BY step_id",
+            id,
+            |row| {
+                Ok(CompletedStep {
+                    step_id: row.get(0)?,
+                    completed_at: row.get(1)?,
+                })
+            },
+        )?,
+        positions: query_rows(
+            conn,
+            "SELECT step_id, slide_index, slide_count FROM step_position WHERE course_id = ?1 ORDER BY step_id",
+            id,
+            |row| {
+                Ok(StepPosition {
+                    step_id: row.get(0)?,
+                    slide_index: row.get(1)?,
+                    slide_count: row.get(2)?,
+                })
+            },
+        )?,
+        slides: query_rows(
+            conn,
+            "SELECT step_id, slide_id FROM slide_completion WHERE course_id = ?1 ORDER BY step_id, slide_id",
+            id,
+            |row| {
+                Ok(CompletedSlide {
+                    step_id: row.get(0)?,
+                    slide_id: row.get(1)?,
+                })
+            },
+        )?,
+        labs: provisioned_labs(conn, id)?,
+        source_url,
+        title,
+    })
+}
+
+fn saved_route(conn: &Connection) -> Result<Option<SavedRoute>, String> {
+    let (kind, course_id, step_index): (String, Option<String>, Option<i64>) = conn
+        .query_row(
+            "SELECT kind, course_id, step_index FROM app_route WHERE id = 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    let source_url = match &course_id {
+        Some(id) => conn
+            .query_row(
+                "SELECT source_url FROM course WHERE id = ?1",
+                params![id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?,
+        None => None,
+    };
+    Ok(match (kind.as_str(), source_url) {
+        ("course", Some(source_url)) => Some(SavedRoute::Course {
+            source_url,
+            step_index: step_index.unwrap_or(0),
+        }),
+        ("editor", Some(source_url)) => Some(SavedRoute::Editor { source_url }),
+        ("browser", _) => Some(SavedRoute::Browser),
+        _ => None,
+    })
+}
+
+/// Writes every course's progress, provisioned labs and the open route to
+/// a JSON file at `dest`. Returns how many courses had progress to export.
+#[tauri::command]
+pub async fn progress_export(db: State<'_, Db>, dest: String) -> Result<usize, String> {
+    let file = {
+        let conn = db.0.lock();
+        let mut stmt = conn
+            .prepare("SELECT id, source_url, title FROM course ORDER BY source_url")
+            .map_err(|e| format!("Failed to query courses: {e}"))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(|e| format!("Failed to read courses: {e}"))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read courses: {e}"))?;
+
+        let mut courses = Vec::new();
+        for (id, source_url, title) in rows {
+            let progress = course_progress(&conn, &id, source_url, title)?;
+            let empty = progress.completed.is_empty()
+                && progress.positions.is_empty()
+                && progress.slides.is_empty()
+                && progress.labs.is_empty();
+            if !empty {
+                courses.push(progress);
+            }
+        }
+        ProgressFile {
+            format: PROGRESS_FORMAT,
+            exported_at: now_ms(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            courses,
+            route: saved_route(&conn)?,
+        }
+    };
+
+    let json = serde_json::to_vec_pretty(&file)
+        .map_err(|e| format!("Failed to serialize progress: {e}"))?;
+    std::fs::write(&dest, json).map_err(|e| format!("Failed to write {dest}: {e}"))?;
+    Ok(file.courses.len())
+}
+
+/// The installed course a source URL names. Pinned and unpinned URLs of
+/// one course share an id, so either form matches.
+fn find_course(conn: &Connection, source_url: &str) -> Result<Option<String>, String> {
+    let forges = crate::settings::current().forges;
+    let id = parse_source_url(source_url, &forges).map(|s| source_id(&s));
+    conn.query_row(
+        "SELECT id FROM course WHERE source_url = ?1 OR id = ?2 ORDER BY source_url = ?1 DESC",
+        params![source_url, id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn step_ids(conn: &Connection, id: &str) -> Result<HashSet<String>, String> {
+    Ok(query_rows(
+        conn,
+        "SELECT step_id FROM course_step WHERE course_id = ?1",
+        id,
+        |row| row.get(0),
+    )?
+    .into_iter()
+    .collect())
+}
+
+/// Merges one course's progress. Steps the installed manifest doesn't
+/// have are skipped and returned.
+fn merge_course(
+    conn: &Connection,
+    id: &str,
+    progress: &CourseProgress,
+    strategy: MergeStrategy,
+) -> Result<BTreeSet<String>, String> {
+    let known = step_ids(conn, id)?;
+    let mut unknown = BTreeSet::new();
+    let mut keep = |step_id: &str| {
+        let found = known.contains(step_id);
+        if !found {
+            unknown.insert(step_id.to_string());
+        }
+        found
+    };
+    let failed = |e: rusqlite::Error| format!("Failed to import progress: {e}");
+
+    if strategy == MergeStrategy::Overwrite {
+        for table in ["step_completion", "step_position", "slide_completion"] {
+            conn.execute(
+                &format!("DELETE FROM {table} WHERE course_id = ?1"),
+                params![id],
+            )
+            .map_err(failed)?;
+        }
+    }
+
+    // Union keeps the earliest completion and the furthest slide.
+    for step in &progress.completed {
+        if keep(&step.step_id) {
+            conn.execute(
+                "INSERT INTO step_completion (course_id, step_id, completed_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (course_id, step_id)
+                 DO UPDATE SET completed_at = min(completed_at, excluded.completed_at)",
+                params![id, &step.step_id, step.completed_at],
+            )
+            .map_err(failed)?;
+        }
+    }
+    for position in &progress.positions {
+        if keep(&position.step_id) {
+            conn.execute(
+                "INSERT INTO step_position (course_id, step_id, slide_index, slide_count)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (course_id, step_id)
+                 DO UPDATE SET slide_index = excluded.slide_index, slide_count = excluded.slide_count
+                 WHERE excluded.slide_index > slide_index",
+                params![
+                    id,
+                    &position.step_id,
+                    position.slide_index,
+                    position.slide_count
+                ],
+            )
+            .map_err(failed)?;
+        }
+    }
+    for slide in &progress.slides {
+        if keep(&slide.step_id) {
+            conn.execute(
+                "INSERT OR IGNORE INTO slide_completion (course_id, step_id, slide_id) VALUES (?1, ?2, ?3)",
+                params![id, &slide.step_id, &slide.slide_id],
+            )
+            .map_err(failed)?;
+        }
+    }
+
+    // A provisioned flag without the workspace would skip setup for an
+    // empty directory, so labs only carry over when the folder came along.
+    for lab in &progress.labs {
+        let Some(slug) = safe_relative(&lab.slug).filter(|p| p.components().count() == 1) else {
+            continue;
+        };
+        let workspace = workspaces_dir().join(id).join(slug);
+        if workspace.is_dir() {
+            conn.execute(
+                "INSERT INTO lab_provision (workspace_path, provisioned_at) VALUES (?1, ?2)
+                 ON CONFLICT (workspace_path) DO NOTHING",
+                params![workspace.to_string_lossy(), lab.provisioned_at],
+            )
+            .map_err(failed)?;
+        }
+    }
+    Ok(unknown)
+}
+
+/// Merges a `progress_export` file into this machine's progress. Courses are
+/// matched by source URL; `union` keeps local progress and adds the file's,
+/// `overwrite` replaces each matched course's progress and restores the route.
+#[tauri::command]
+pub async fn progress_import(
+    db: State<'_, Db>,
+    path: String,
+    strategy: MergeStrategy,
+) -> Result<ProgressImport, String> {
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) => {
+            return Ok(ProgressImport::BadFile {
+                reason: format!("Failed to read {path}: {e}"),
+            });
+        }
+    };
+    let file: ProgressFile = match serde_json::from_str(&text) {
+        Ok(file) => file,
+        Err(e) => {
+            return Ok(ProgressImport::BadFile {
+                reason: format!("Not a progress export: {e}"),
+            });
+        }
+    };
+    if file.format > PROGRESS_FORMAT {
+        return Ok(ProgressImport::BadFile {
+            reason: format!(
+                "Progress format {} is newer than this app supports ({PROGRESS_FORMAT})",
+                file.format
+            ),
+        });
+    }
+
+    let mut conn = db.0.lock();
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin import: {e}"))?;
+
+    let mut courses = 0;
+    let mut unmatched_courses = Vec::new();
+    let mut unmatched_steps = Vec::new();
+    for progress in &file.courses {
+        let Some(id) = find_course(&tx, &progress.source_url)? else {
+            unmatched_courses.push(UnmatchedCourse {
+                source_url: progress.source_url.clone(),
+                title: progress.title.clone(),
+            });
+            continue;
+        };
+        let unknown = merge_course(&tx, &id, progress, strategy)?;
+        unmatched_steps.extend(unknown.into_iter().map(|step_id| UnmatchedStep {
+            source_url: progress.source_url.clone(),
+            step_id,
+        }));
+        courses += 1;
+    }
+
+    if strategy == MergeStrategy::Overwrite {
+        let route = match &file.route {
+            Some(SavedRoute::Browser) => Some(Route::Browser),
+            Some(SavedRoute::Course {
+                source_url,
+                step_index,
+            }) => find_course(&tx, source_url)?.map(|course_id| Route::Course {
+                course_id,
+                step_index: *step_index,
+            }),
+            Some(SavedRoute::Editor { source_url }) => {
+                find_course(&tx, source_url)?.map(|course_id| Route::Editor { course_id })
+            }
+            None => None,
+        };
+        if let Some(route) = route {
+            save_route(&tx, &route)?;
+        }
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit import: {e}"))?;
+    Ok(ProgressImport::Ok {
+        courses,
+        unmatched_courses,
+        unmatched_steps,
+    })
+}
@@ -0,0 +1,271 @@
+use parking_lot::Mutex;
+use reqwest::StatusCode;
+use reqwest::blocking::Response;
+use reqwest::header::{
+    CONTENT_RANGE, ETAG, HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE,
+    LAST_MODIFIED, RANGE,
+};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::paths::download_cache_dir;
+
+use super::credentials::get_blocking_with;
+use super::download::{ProgressReader, Transfer};
+use super::source::hash_id;
+use super::types::ImportEvent;
+
+/// Least recently used downloads are evicted once the cache grows past this.
+const MAX_CACHE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Keys being downloaded right now, so two imports never share a `.part` file.
+static IN_FLIGHT: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Response validators stored next to a download, for revalidating a
+/// finished copy and for `If-Range` when resuming a partial one.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Validators {
+    url: String,
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    last_modified: Option<String>,
+}
+
+impl Validators {
+    fn from_response(url: &str, resp: &Response) -> Self {
+        let header = |name| {
+            resp.headers()
+                .get(name)
+                .and_then(|v: &HeaderValue| v.to_str().ok())
+                .map(str::to_string)
+        };
+        Validators {
+            url: url.to_string(),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+
+    /// Weak ETags can't be used with `If-Range`, so those fall back to the date.
+    fn if_range(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|tag| !tag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+}
+
+/// Files for one cache key: the finished download, the partial one, and
+/// their validators.
+struct Entry {
+    key: String,
+    complete: PathBuf,
+    part: PathBuf,
+    meta: PathBuf,
+}
+
+impl Entry {
+    fn new(url: &str, revision: Option<&str>) -> Self {
+        let key = hash_id(&format!("{url}\n{}", revision.unwrap_or_default()));
+        let dir = download_cache_dir();
+        Entry {
+            complete: dir.join(format!("{key}.download")),
+            part: dir.join(format!("{key}.part")),
+            meta: dir.join(format!("{key}.json")),
+            key,
+        }
+    }
+
+    fn validators(&self) -> Option<Validators> {
+        let text = std::fs::read_to_string(&self.meta).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    fn save_validators(&self, validators: &Validators) -> Result<(), String> {
+        let text = serde_json::to_string(validators).map_err(|e| e.to_string())?;
+        std::fs::write(&self.meta, text).map_err(|e| format!("Failed to write download cache: {e}"))
+    }
+}
+
+/// Releases a key from `IN_FLIGHT` when the download ends, however it ends.
+struct Claim(String);
+
+impl Claim {
+    fn take(key: &str) -> Option<Claim> {
+        let mut in_flight = IN_FLIGHT.lock();
+        if in_flight.iter().any(|k| k == key) {
+            return None;
+        }
+        in_flight.push(key.to_string());
+        Some(Claim(key.to_string()))
+    }
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        IN_FLIGHT.lock().retain(|k| k != &self.0);
+    }
+}
+
+/// Downloads `url` into the cache, or reuses the copy an earlier import
+/// left there, and returns the finished file.
+///
+/// With a `revision` the content can't change, so a finished copy is used
+/// without asking the server. Without one it is revalidated by ETag or
+/// Last-Modified. An interrupted download resumes with a range request.
+/// `Ok(None)` when another import is already downloading the same key.
+pub(super) fn cached_download(
+    url: &str,
+    revision: Option<&str>,
+    transfer: &Transfer,
+) -> Result<Option<PathBuf>, String> {
+    let entry = Entry::new(url, revision);
+    let Some(_claim) = Claim::take(&entry.key) else {
+        return Ok(None);
+    };
+    std::fs::create_dir_all(download_cache_dir())
+        .map_err(|e| format!("Failed to create download cache: {e}"))?;
+
+    let saved = entry.validators().filter(|v| v.url == url);
+    let cached = entry.complete.exists();
+    if cached && revision.is_some() {
+        return reuse(&entry, transfer).map(Some);
+    }
+
+    let mut headers = HeaderMap::new();
+    let mut offset = 0;
+    if cached {
+        if let Some(etag) = saved.as_ref().and_then(|v| v.etag.as_deref()) {
+            insert_header(&mut headers, IF_NONE_MATCH, etag);
+        }
+        if let Some(date) = saved.as_ref().and_then(|v| v.last_modified.as_deref()) {
+            insert_header(&mut headers, IF_MODIFIED_SINCE, date);
+        }
+    } else if let Some(validator) = saved.as_ref().and_then(Validators::if_range) {
+        offset = std::fs::metadata(&entry.part).map(|m| m.len()).unwrap_or(0);
+        if offset > 0 {
+            insert_header(&mut headers, RANGE, &format!("bytes={offset}-"));
+            insert_header(&mut headers, IF_RANGE, validator);
+        }
+    }
+
+    let resp =
+        get_blocking_with(url, &headers).map_err(|e| format!("Failed to download tarball: {e}"))?;
+    let append = match resp.status() {
+        StatusCode::NOT_MODIFIED if cached => return reuse(&entry, transfer).map(Some),
+        StatusCode::PARTIAL_CONTENT if offset > 0 && range_start(&resp) == Some(offset) => true,
+        StatusCode::OK => false,
+        StatusCode::PARTIAL_CONTENT => {
+            let _ = std::fs::remove_file(&entry.part);
+            return Err(
+                "Failed to download tarball: server resumed at the wrong offset".to_string(),
+            );
+        }
+        status => return Err(format!("Failed to download tarball: HTTP {status}")),
+    };
+    if !append {
+        offset = 0;
+        // The old copy is stale now; left in place, an interrupted download
+        // would revalidate it against the new validators and reuse it.
+        let _ = std::fs::remove_file(&entry.complete);
+        // Saved before the body so an interrupted download can resume.
+        entry.save_validators(&Validators::from_response(url, &resp))?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(&entry.part)
+        .map_err(|e| format!("Failed to write download cache: {e}"))?;
+    let total = resp.content_length().map(|len| len + offset);
+    let mut reader = ProgressReader::new(resp, transfer, total, offset);
+    std::io::copy(&mut reader, &mut file)
+        .map_err(|e| format!("Failed to download tarball: {e}"))?;
+    drop(file);
+
+    std::fs::rename(&entry.part, &entry.complete)
+        .map_err(|e| format!("Failed to write download cache: {e}"))?;
+    trim(&entry.complete);
+    Ok(Some(entry.complete))
+}
+
+/// Drops a finished download that turned out to be unusable, so the next
+/// import fetches it again instead of failing the same way.
+pub(super) fn evict(complete: &Path) {
+    let _ = std::fs::remove_file(complete);
+    let _ = std::fs::remove_file(complete.with_extension("json"));
+}
+
+fn reuse(entry: &Entry, transfer: &Transfer) -> Result<PathBuf, String> {
+    let len = std::fs::metadata(&entry.complete)
+        .map_err(|e| format!("Failed to read download cache: {e}"))?
+        .len();
+    touch(&entry.complete);
+    (transfer.on_event)(ImportEvent::Downloaded {
+        bytes: len,
+        total: Some(len),
+    });
+    Ok(entry.complete.clone())
+}
+
+fn insert_header(headers: &mut HeaderMap, name: reqwest::header::HeaderName, value: &str) {
+    if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(name, value);
+    }
+}
+
+/// First byte of a `Content-Range: bytes 100-199/200` response.
+fn range_start(resp: &Response) -> Option<u64> {
+    let value = resp.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let (start, _) = value.strip_prefix("bytes ")?.split_once('-')?;
+    start.parse().ok()
+}
+
+/// Marks a download as recently used, for eviction order.
+fn touch(path: &Path) {
+    if let Ok(file) = File::options().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+/// Evicts the least recently used downloads, partial ones included, until
+/// the cache fits in `MAX_CACHE_BYTES`. `keep` and downloads still in
+/// progress are never evicted.
+fn trim(keep: &Path) {
+    let Ok(entries) = std::fs::read_dir(download_cache_dir()) else {
+        return;
+    };
+    let in_flight = IN_FLIGHT.lock().clone();
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext != "json"))
+        .filter(|e| {
+            let stem = e
+                .path()
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned());
+            !stem.is_some_and(|stem| in_flight.contains(&stem))
+        })
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            Some((meta.modified().ok()?, meta.len(), e.path()))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort_by_key(|(modified, _, _)| *modified);
+    for (_, len, path) in files {
+        if total <= MAX_CACHE_BYTES {
+            break;
+        }
+        if path != keep {
+            evict(&path);
+            total -= len;
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use crate::db::Db;
+use rusqlite::{Connection, params};
+use serde::Deserialize;
+use tauri::State;
+use tauri::ipc::Channel;
+
+use super::credentials;
+use super::import::{ActiveImports, run_import};
+use super::now_ms;
+use super::source::{CourseSource, canonical_source_url, parse_source_url, pin, source_id};
+use super::types::{CatalogEntry, CatalogRefresh, ImportEvent, ImportResult};
+
+/// A catalog index, as published at a URL or kept in a local file:
+///
+/// ```json
+/// { "courses": [{ "title": "...", "description": "...", "tags": ["rust"],
+///                 "sourceUrl": "https://github.com/o/r", "revision": "v1.2" }] }
+/// ```
+#[derive(Deserialize)]
+struct CatalogIndex {
+    courses: Vec<CatalogCourse>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CatalogCourse {
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    source_url: String,
+    #[serde(default)]
+    revision: Option<String>,
+}
+
+fn is_remote(location: &str) -> bool {
+    location.starts_with("https://") || location.starts_with("http://")
+}
+
+async fn read_catalog(location: &str) -> Result<CatalogIndex, String> {
+    let text = if is_remote(location) {
+        credentials::get(location)
+            .await
+            .and_then(|r| r.error_for_status().map_err(|e| e.to_string()))
+            .map_err(|e| format!("Failed to fetch catalog: {e}"))?
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read catalog: {e}"))?
+    } else {
+        let path = location.strip_prefix("file://").unwrap_or(location);
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read catalog: {e}"))?
+    };
+    serde_json::from_str(&text).map_err(|e| format!("Invalid catalog: {e}"))
+}
+
+/// Replaces the cached entries for `catalog`. Returns (stored, skipped).
+/// A course listed by several catalogs keeps the entry it was first seen with.
+fn store_catalog(
+    conn: &mut Connection,
+    catalog: &str,
+    index: &CatalogIndex,
+) -> Result<(usize, usize), String> {
+    let forges = crate::settings::current().forges;
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin catalog update: {e}"))?;
+    tx.execute(
+        "DELETE FROM catalog_entry WHERE catalog = ?1",
+        params![catalog],
+    )
+    .map_err(|e| format!("Failed to clear catalog: {e}"))?;
+
+    let fetched_at = now_ms();
+    let (mut stored, mut skipped) = (0, 0);
+    for course in &index.courses {
+        let Some(source) = parse_source_url(&course.source_url, &forges) else {
+            skipped += 1;
+            continue;
+        };
+        let source_url = canonical_source_url(&source);
+        let inserted = tx
+            .execute(
+                "INSERT OR IGNORE INTO catalog_entry
+                 (source_url, course_id, catalog, title, description, revision, fetched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    &source_url,
+                    source_id(&source),
+                    catalog,
+                    &course.title,
+                    &course.description,
+                    &course.revision,
+                    fetched_at
+                ],
+            )
+            .map_err(|e| format!("Failed to insert catalog entry: {e}"))?;
+        if inserted == 0 {
+            continue;
+        }
+        for tag in course.tags.iter().filter(|t| !t.is_empty()) {
+            tx.execute(
+                "INSERT OR IGNORE INTO catalog_tag (source_url, name) VALUES (?1, ?2)",
+                params![&source_url, tag],
+            )
+            .map_err(|e| format!("Failed to insert catalog tag: {e}"))?;
+        }
+        stored += 1;
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit catalog update: {e}"))?;
+    Ok((stored, skipped))
+}
+
+/// Case-insensitive substring match on title and description, or an exact
+/// tag match. An empty query matches every entry.
+fn search_entries(conn: &Connection, query: &str) -> Result<Vec<CatalogEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT e.source_url, e.catalog, e.title, e.description, e.revision,
+                    EXISTS(SELECT 1 FROM course c WHERE c.id = e.course_id)
+             FROM catalog_entry e
+             WHERE instr(lower(e.title), lower(?1)) > 0
+                OR instr(lower(e.description), lower(?1)) > 0
+                OR EXISTS(SELECT 1 FROM catalog_tag t
+                          WHERE t.source_url = e.source_url AND lower(t.name) = lower(?1))
+             ORDER BY e.title COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+    let mut entries = stmt
+        .query_map(params![query], |row| {
+            Ok(CatalogEntry {
+                source_url: row.get(0)?,
+                catalog: row.get(1)?,
+                title: row.get(2)?,
+                description: row.get(3)?,
+                tags: Vec::new(),
+                revision: row.get(4)?,
+                installed: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut tag_stmt = conn
+        .prepare("SELECT name FROM catalog_tag WHERE source_url = ?1 ORDER BY name")
+        .map_err(|e| e.to_string())?;
+    for entry in &mut entries {
+        entry.tags = tag_stmt
+            .query_map(params![&entry.source_url], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(entries)
+}
+
+/// Re-reads every catalog in settings and caches its entries. Catalogs no
+/// longer configured are dropped from the cache.
+#[tauri::command]
+pub async fn catalog_refresh(db: State<'_, Db>) -> Result<Vec<CatalogRefresh>, String> {
+    let catalogs = crate::settings::current().catalogs;
+
+    {
+        let conn = db.0.lock();
+        let placeholders = vec!["?"; catalogs.len()].join(", ");
+        conn.execute(
+            &format!("DELETE FROM catalog_entry WHERE catalog NOT IN ({placeholders})"),
+            rusqlite::params_from_iter(&catalogs),
+        )
+        .map_err(|e| format!("Failed to prune catalogs: {e}"))?;
+    }
+
+    let mut results = Vec::with_capacity(catalogs.len());
+    for catalog in catalogs {
+        let index = match read_catalog(&catalog).await {
+            Ok(index) => index,
+            Err(reason) => {
+                results.push(CatalogRefresh::Failed { catalog, reason });
+                continue;
+            }
+        };
+        let mut conn = db.0.lock();
+        results.push(match store_catalog(&mut conn, &catalog, &index) {
+            Ok((courses, skipped)) => CatalogRefresh::Ok {
+                catalog,
+                courses,
+                skipped,
+            },
+            Err(reason) => CatalogRefresh::Failed { catalog, reason },
+        });
+    }
+    Ok(results)
+}
+
+/// Every cached catalog entry, whether or not it's installed.
+#[tauri::command]
+pub async fn catalog_list(db: State<'_, Db>) -> Result<Vec<CatalogEntry>, String> {
+    let conn = db.0.lock();
+    search_entries(&conn, "")
+}
+
+#[tauri::command]
+pub async fn catalog_search(db: State<'_, Db>, query: String) -> Result<Vec<CatalogEntry>, String> {
+    let conn = db.0.lock();
+    search_entries(&conn, query.trim())
+}
+
+/// Imports a catalog entry, pinned to the catalog's revision when it gives
+/// one. Cancel with `course_import_cancel(source_url)`.
+#[tauri::command]
+pub async fn catalog_install(
+    db: State<'_, Db>,
+    imports: State<'_, ActiveImports>,
+    source_url: String,
+    on_event: Channel<ImportEvent>,
+) -> Result<ImportResult, String> {
+    let revision: Option<String> = {
+        let conn = db.0.lock();
+        conn.query_row(
+            "SELECT revision FROM catalog_entry WHERE source_url = ?1",
+            params![&source_url],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Catalog entry not found: {e}"))?
+    };
+
+    // Plain HTTP sources can't be pinned, and a catalog URL that names a
+    // ref (`@ref` or `/tree/{ref}`) already is a pin.
+    let forges = crate::settings::current().forges;
+    let pinnable = parse_source_url(&source_url, &forges)
+        .is_some_and(|s| !matches!(s, CourseSource::Http { .. }) && pin(&s).is_none());
+    let install_url = match revision {
+        Some(rev) if pinnable => format!("{source_url}@{rev}"),
+        _ => source_url.clone(),
+    };
+
+    let emit = |event: ImportEvent| {
+        let _ = on_event.send(event);
+    };
+    run_import(&db, &imports, &source_url, &install_url, &emit).await
+}
@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::paths::credentials_path;
+
+/// Secret attached to every request for one host. Kept in its own file,
+/// outside `settings.json`, so settings can be shared without leaking tokens.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Credential {
+    /// GitHub, GitLab and Gitea personal access tokens, or any bearer token.
+    /// Sent as `Authorization` so reqwest drops it on cross-host redirects.
+    Bearer { token: String },
+    /// Bitbucket app passwords, or HTTP basic auth on a plain web server.
+    Basic { username: String, password: String },
+}
+
+/// A stored credential as `credential_list` reports it, without the secret.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialEntry {
+    pub host: String,
+    pub kind: &'static str,
+}
+
+/// Hosts a forge serves its API and raw files from, mapped to the host a
+/// user adds credentials for.
+const HOST_ALIASES: &[(&str, &str)] = &[
+    ("api.github.com", "github.com"),
+    ("raw.githubusercontent.com", "github.com"),
+    ("api.bitbucket.org", "bitbucket.org"),
+];
+
+fn read_store() -> Result<BTreeMap<String, Credential>, String> {
+    let path = credentials_path();
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read credentials: {e}"))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse credentials: {e}"))
+}
+
+fn write_store(store: &BTreeMap<String, Credential>) -> Result<(), String> {
+    let path = credentials_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create credentials dir: {e}"))?;
+    }
+    let content = serde_json::to_string_pretty(store)
+        .map_err(|e| format!("Failed to serialize credentials: {e}"))?;
+
+    // Written to a file that is private from the moment it exists, then
+    // renamed over the old one, so the secrets are never world-readable.
+    let tmp = path.with_extension("json.tmp");
+    let _ = std::fs::remove_file(&tmp);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options.open(&tmp).and_then(|mut file| {
+        use std::io::Write;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|()| std::fs::rename(&tmp, &path)) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(format!("Failed to write credentials: {e}"));
+    }
+    Ok(())
+}
+
+/// `host[:port]` of `url`, with forge API and raw hosts folded into the forge host.
+pub(super) fn credential_host(url: &str) -> Option<String> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    let host = parsed.host_str()?.to_ascii_lowercase();
+    let host = HOST_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == host)
+        .map_or(host.clone(), |(_, canonical)| canonical.to_string());
+    Some(match parsed.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host,
+    })
+}
+
+/// Only HTTPS requests carry credentials; over plain HTTP the secret would
+/// cross the network in the clear.
+fn credential_for(url: &str) -> Option<Credential> {
+    if reqwest::Url::parse(url).ok()?.scheme() != "https" {
+        return None;
+    }
+    read_store().ok()?.remove(&credential_host(url)?)
+}
+
+pub(super) fn has_credential(url: &str) -> bool {
+    credential_for(url).is_some()
+}
+
+/// `Authorization` value for a request to `url`, if the user stored a credential for its host.
+fn auth_header(url: &str) -> Option<String> {
+    Some(match credential_for(url)? {
+        Credential::Bearer { token } => format!("Bearer {token}"),
+        Credential::Basic { username, password } => {
+            use base64::Engine;
+            let encoded =
+                base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"));
+            format!("Basic {encoded}")
+        }
+    })
+}
+
+/// Async GET with the stored credential for the URL's host attached.
+pub(super) async fn get(url: &str) -> Result<reqwest::Response, String> {
+    crate::http::get(url, auth_header).await
+}
+
+/// Blocking GET with the stored credential for the URL's host attached.
+pub(super) fn get_blocking(url: &str) -> Result<reqwest::blocking::Response, String> {
+    crate::http::get_blocking(url, auth_header)
+}
+
+/// `get_blocking` with extra request headers.
+pub(super) fn get_blocking_with(
+    url: &str,
+    headers: &reqwest::header::HeaderMap,
+) -> Result<reqwest::blocking::Response, String> {
+    crate::http::get_blocking_with(url, auth_header, headers)
+}
+
+#[tauri::command]
+pub async fn credential_list() -> Result<Vec<CredentialEntry>, String> {
+    Ok(read_store()?
+        .into_iter()
+        .map(|(host, credential)| CredentialEntry {
+            host,
+            kind: match credential {
+                Credential::Bearer { .. } => "bearer",
+                Credential::Basic { .. } => "basic",
+            },
+        })
+        .collect())
+}
+
+/// Adds or replaces the credential for `host` (`git.example.com` or `git.example.com:8443`).
+#[tauri::command]
+pub async fn credential_set(host: String, credential: Credential) -> Result<(), String> {
+    let host = host.trim().to_ascii_lowercase();
+    if host.is_empty() || host.contains('/') {
+        return Err("Host must be a bare hostname, like github.com".to_string());
+    }
+    let mut store = read_store()?;
+    store.insert(host, credential);
+    write_store(&store)
+}
+
+#[tauri::command]
+pub async fn credential_remove(host: String) -> Result<(), String> {
+    let mut store = read_store()?;
+    store.remove(&host.trim().to_ascii_lowercase());
+    write_store(&store)
+}
@@ -0,0 +1,200 @@
+use crate::db::Db;
+use parking_lot::Mutex;
+use std::path::Path;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager, State, Url};
+
+use super::gating::installed_manifest;
+use super::progress::save_route;
+use super::source::parse_source_url;
+use super::types::{DeepLinkEvent, Manifest, Route, StepKind};
+
+const SCHEME: &str = "handhold";
+
+/// A parsed `handhold://` link.
+enum DeepLink {
+    /// `handhold://import?url={source}`
+    Import { source_url: String },
+    /// `handhold://course/{id}/step/{n}`, `n` counting from 1 as the UI does.
+    /// Also the older `handhold://open/{id}/{index}`, counting from 0.
+    Step {
+        course_id: String,
+        step_index: usize,
+    },
+    /// `handhold://lab/{id}/{slug}`, `slug` being a lab step's id or folder name.
+    Lab { course_id: String, slug: String },
+}
+
+/// Where link outcomes go: the frontend's channel once it subscribes, a
+/// queue until then, so a link that launched the app isn't lost.
+#[derive(Default)]
+pub struct DeepLinks(Mutex<Subscriber>);
+
+#[derive(Default)]
+struct Subscriber {
+    channel: Option<Channel<DeepLinkEvent>>,
+    pending: Vec<DeepLinkEvent>,
+}
+
+impl DeepLinks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn send(&self, event: DeepLinkEvent) {
+        let mut subscriber = self.0.lock();
+        match &subscriber.channel {
+            Some(channel) => {
+                let _ = channel.send(event);
+            }
+            None => subscriber.pending.push(event),
+        }
+    }
+}
+
+fn parse(url: &Url) -> Result<DeepLink, String> {
+    if url.scheme() != SCHEME {
+        return Err(format!("Not a {SCHEME}:// link"));
+    }
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|s| s.filter(|p| !p.is_empty()).collect())
+        .unwrap_or_default();
+    let number = |text: &str| {
+        text.parse::<usize>()
+            .map_err(|_| format!("Invalid step number: {text}"))
+    };
+
+    match (url.host_str().unwrap_or_default(), segments.as_slice()) {
+        ("import", []) => {
+            let source_url = url
+                .query_pairs()
+                .find(|(key, _)| key == "url")
+                .map(|(_, value)| value.into_owned())
+                .ok_or("Import link has no url parameter")?;
+            let forges = crate::settings::current().forges;
+            if parse_source_url(&source_url, &forges).is_none() {
+                return Err(format!("Not a course URL: {source_url}"));
+            }
+            Ok(DeepLink::Import { source_url })
+        }
+        ("course", [id]) => Ok(DeepLink::Step {
+            course_id: id.to_string(),
+            step_index: 0,
+        }),
+        ("course", [id, "step", n]) => match number(n)? {
+            0 => Err("Step numbers start at 1".to_string()),
+            n => Ok(DeepLink::Step {
+                course_id: id.to_string(),
+                step_index: n - 1,
+            }),
+        },
+        ("open", [id]) => Ok(DeepLink::Step {
+            course_id: id.to_string(),
+            step_index: 0,
+        }),
+        ("open", [id, index]) => Ok(DeepLink::Step {
+            course_id: id.to_string(),
+            step_index: number(index)?,
+        }),
+        ("lab", [id, slug]) => Ok(DeepLink::Lab {
+            course_id: id.to_string(),
+            slug: slug.to_string(),
+        }),
+        _ => Err("Unrecognized link".to_string()),
+    }
+}
+
+/// The route a navigation link opens, checked against the installed course.
+fn resolve(manifest: &Manifest, course_id: &str, target: &Target) -> Result<Route, String> {
+    let step_index = match target {
+        &Target::Index(index) if index < manifest.steps.len() => index,
+        &Target::Index(index) => {
+            return Err(format!(
+                "Step {} doesn't exist; the course has {}",
+                index + 1,
+                manifest.steps.len()
+            ));
+        }
+        Target::Lab(slug) => manifest
+            .steps
+            .iter()
+            .position(|step| {
+                step.kind == StepKind::Lab
+                    && (step.id == *slug
+                        || Path::new(&step.path)
+                            .file_name()
+                            .is_some_and(|n| n == slug.as_str()))
+            })
+            .ok_or_else(|| format!("No lab named {slug}"))?,
+    };
+    Ok(Route::Course {
+        course_id: course_id.to_string(),
+        step_index: step_index as i64,
+    })
+}
+
+/// The step a navigation link points at, before it's checked.
+enum Target {
+    Index(usize),
+    Lab(String),
+}
+
+/// Saves `route` as the current one and tells the frontend to follow it.
+fn navigate(app: &AppHandle, route: Route) -> Result<(), String> {
+    {
+        let db = app.state::<Db>();
+        let conn = db.0.lock();
+        save_route(&conn, &route)?;
+    }
+    app.state::<DeepLinks>()
+        .send(DeepLinkEvent::Navigate { route });
+    Ok(())
+}
+
+fn open(app: &AppHandle, url: &Url) -> Result<(), String> {
+    let (course_id, target) = match parse(url)? {
+        DeepLink::Import { source_url } => {
+            app.state::<DeepLinks>()
+                .send(DeepLinkEvent::ImportRequested { source_url });
+            return Ok(());
+        }
+        DeepLink::Step {
+            course_id,
+            step_index,
+        } => (course_id, Target::Index(step_index)),
+        DeepLink::Lab { course_id, slug } => (course_id, Target::Lab(slug)),
+    };
+    let manifest = installed_manifest(&app.state::<Db>(), &course_id)?;
+    let route = resolve(&manifest, &course_id, &target)?;
+    navigate(app, route)
+}
+
+/// Routes `handhold://` links from the OS, including ones forwarded from a
+/// second launch. Links that don't check out are reported as `Rejected`.
+pub fn open_deep_links(app: &AppHandle, urls: &[Url]) {
+    for url in urls {
+        if let Err(reason) = open(app, url) {
+            eprintln!("[deep-link] {url}: {reason}");
+            app.state::<DeepLinks>().send(DeepLinkEvent::Rejected {
+                url: url.to_string(),
+                reason,
+            });
+        }
+    }
+}
+
+/// Streams deep-link outcomes to the frontend, starting with any that
+/// arrived before it subscribed.
+#[tauri::command]
+pub async fn deep_link_subscribe(
+    links: State<'_, DeepLinks>,
+    on_event: Channel<DeepLinkEvent>,
+) -> Result<(), String> {
+    let mut subscriber = links.0.lock();
+    for event in subscriber.pending.drain(..) {
+        let _ = on_event.send(event);
+    }
+    subscriber.channel = Some(on_event);
+    Ok(())
+}
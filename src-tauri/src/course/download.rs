@@ -1,30 +1,221 @@
-use std::io::Read;
-use std::path::Path;
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::{Component, Path};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use flate2::read::GzDecoder;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use tar::Archive;
 
-use super::types::Manifest;
+use super::cache::{cached_download, evict};
+use super::credentials::get_blocking;
+use super::skipped_dir;
+use super::types::{ImportEvent, Manifest};
 
-/// Download a course from a GitHub repo via tarball, extracting only the subpath.
-pub(super) fn download_github_course(
-    owner: &str,
-    repo: &str,
-    branch: &str,
+/// Bytes between `Downloaded` events, so a fast connection doesn't flood the channel.
+const REPORT_EVERY_BYTES: u64 = 256 * 1024;
+/// Files between `Extracted` events.
+const REPORT_EVERY_FILES: usize = 100;
+
+static NEVER_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+fn ignore_event(_: ImportEvent) {}
+
+/// Progress sink and cancel flag threaded through a download.
+pub(super) struct Transfer<'a> {
+    pub on_event: &'a (dyn Fn(ImportEvent) + Sync),
+    pub cancelled: &'a AtomicBool,
+}
+
+impl Transfer<'_> {
+    /// Reports nothing and can't be cancelled. For callers without a progress UI.
+    pub(super) fn silent() -> Transfer<'static> {
+        Transfer {
+            on_event: &ignore_event,
+            cancelled: &NEVER_CANCELLED,
+        }
+    }
+
+    pub(super) fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn check_cancelled(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err("Import cancelled".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Counts compressed bytes as they arrive and aborts the read once cancelled.
+pub(super) struct ProgressReader<'a, R> {
+    inner: R,
+    transfer: &'a Transfer<'a>,
+    total: Option<u64>,
+    bytes: u64,
+    reported: u64,
+}
+
+impl<'a, R> ProgressReader<'a, R> {
+    /// `start` is the byte count already on disk when resuming.
+    pub(super) fn new(
+        inner: R,
+        transfer: &'a Transfer<'a>,
+        total: Option<u64>,
+        start: u64,
+    ) -> Self {
+        ProgressReader {
+            inner,
+            transfer,
+            total,
+            bytes: start,
+            reported: start,
+        }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.transfer.is_cancelled() {
+            return Err(std::io::Error::other("Import cancelled"));
+        }
+        let n = self.inner.read(buf)?;
+        self.bytes += n as u64;
+        if n == 0 || self.bytes - self.reported >= REPORT_EVERY_BYTES {
+            self.reported = self.bytes;
+            (self.transfer.on_event)(ImportEvent::Downloaded {
+                bytes: self.bytes,
+                total: self.total,
+            });
+        }
+        Ok(n)
+    }
+}
+
+/// Download a course from a forge repo tarball, extracting only the subpath.
+/// The tarball goes through the download cache, so courses from one repo
+/// at one revision share a single download.
+pub(super) fn download_archive_course(
+    tarball_url: &str,
+    revision: Option<&str>,
     subpath: &str,
     dest: &Path,
+    transfer: &Transfer,
 ) -> Result<(), String> {
-    let tarball_url =
-        format!("https://github.com/{owner}/{repo}/archive/refs/heads/{branch}.tar.gz");
+    let Some(archive) = cached_download(tarball_url, revision, transfer)? else {
+        return stream_archive_course(tarball_url, subpath, dest, transfer);
+    };
+    let file = File::open(&archive).map_err(|e| format!("Failed to read tarball: {e}"))?;
+    let extracted = extract_tarball(BufReader::new(file), subpath, dest, transfer);
+    if extracted.is_err() && !transfer.is_cancelled() {
+        evict(&archive);
+    }
+    extracted
+}
 
-    let resp = reqwest::blocking::get(&tarball_url)
-        .and_then(reqwest::blocking::Response::error_for_status)
+/// Extracts straight from the response, for when another import holds the cache entry.
+fn stream_archive_course(
+    tarball_url: &str,
+    subpath: &str,
+    dest: &Path,
+    transfer: &Transfer,
+) -> Result<(), String> {
+    let resp = get_blocking(tarball_url)
+        .and_then(|r| r.error_for_status().map_err(|e| e.to_string()))
         .map_err(|e| format!("Failed to download tarball: {e}"))?;
 
-    let decoder = GzDecoder::new(resp);
+    let total = resp.content_length();
+    extract_tarball(
+        ProgressReader::new(resp, transfer, total, 0),
+        subpath,
+        dest,
+        transfer,
+    )
+}
+
+/// Most bytes a single course may unpack to.
+const MAX_EXTRACTED_BYTES: u64 = 1024 * 1024 * 1024;
+/// Most files and directories a single course may unpack to.
+const MAX_EXTRACTED_ENTRIES: usize = 50_000;
+/// Most bytes read out of the gzip stream, including entries outside the
+/// subpath. Catches compression bombs hidden in parts we'd otherwise skip.
+const MAX_DECOMPRESSED_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Caps on what one archive may unpack to. Tests shrink them.
+struct Limits {
+    bytes: u64,
+    entries: usize,
+    decompressed: u64,
+}
+
+const LIMITS: Limits = Limits {
+    bytes: MAX_EXTRACTED_BYTES,
+    entries: MAX_EXTRACTED_ENTRIES,
+    decompressed: MAX_DECOMPRESSED_BYTES,
+};
+
+/// Reader that fails once more than `limit` bytes have passed through.
+struct CappedReader<R> {
+    inner: R,
+    limit: u64,
+    remaining: u64,
+}
+
+impl<R: Read> Read for CappedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.remaining = self.remaining.checked_sub(n as u64).ok_or_else(|| {
+            std::io::Error::other(format!(
+                "archive decompresses to more than {} MiB",
+                self.limit / (1024 * 1024)
+            ))
+        })?;
+        Ok(n)
+    }
+}
+
+/// Returns `relative` if it stays inside the directory it is joined onto:
+/// no `..`, no root, no drive prefix.
+pub(super) fn safe_relative(relative: &str) -> Option<&Path> {
+    let path = Path::new(relative);
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        .then_some(path)
+}
+
+/// Unpack a gzipped tarball into `dest`, keeping only entries under `subpath`.
+///
+/// Entries that would land outside `dest` abort the extraction, as does
+/// exceeding the size or entry limits. Symlinks, hardlinks and device
+/// nodes are skipped: course content never needs them, and a link is the
+/// usual way to redirect a later write outside the course directory.
+pub(super) fn extract_tarball(
+    reader: impl Read,
+    subpath: &str,
+    dest: &Path,
+    transfer: &Transfer,
+) -> Result<(), String> {
+    extract_tarball_within(reader, subpath, dest, transfer, &LIMITS)
+}
+
+fn extract_tarball_within(
+    reader: impl Read,
+    subpath: &str,
+    dest: &Path,
+    transfer: &Transfer,
+    limits: &Limits,
+) -> Result<(), String> {
+    let decoder = CappedReader {
+        inner: GzDecoder::new(reader),
+        limit: limits.decompressed,
+        remaining: limits.decompressed,
+    };
     let mut archive = Archive::new(decoder);
 
-    // GitHub tarballs have a top-level prefix dir like `{repo}-{branch}/`.
+    // Forge tarballs have a single top-level prefix dir like `{repo}-{branch}/`.
     // We strip that prefix and match entries under `{prefix}/{subpath}/`.
     let prefix_with_subpath = if subpath.is_empty() {
         String::new()
@@ -32,10 +223,14 @@ pub(super) fn download_github_course(
         format!("/{subpath}/")
     };
 
+    let mut extracted_bytes: u64 = 0;
+    let mut extracted_entries: usize = 0;
+
     for entry in archive
         .entries()
         .map_err(|e| format!("Failed to read tarball: {e}"))?
     {
+        transfer.check_cancelled()?;
         let mut entry = entry.map_err(|e| format!("Tarball entry error: {e}"))?;
         let raw_path = entry
             .path()
@@ -43,6 +238,13 @@ pub(super) fn download_github_course(
             .to_path_buf();
 
         let raw_str = raw_path.to_string_lossy();
+        // Checked before the prefix is stripped, which would turn `/etc/x`
+        // into a harmless-looking `etc/x`.
+        if safe_relative(&raw_str).is_none() {
+            return Err(format!(
+                "Archive entry escapes the course directory: {raw_str}"
+            ));
+        }
 
         // Strip the top-level prefix directory (everything before the first `/`).
         let after_prefix = match raw_str.find('/') {
@@ -64,9 +266,33 @@ pub(super) fn download_github_course(
             continue;
         }
 
-        let out_path = dest.join(relative);
+        let relative_path = Path::new(relative);
+
+        let entry_type = entry.header().entry_type();
+        if !entry_type.is_dir() && !entry_type.is_file() {
+            continue;
+        }
+        // Not course files; local copies and integrity checks skip them too.
+        let dirs = if entry_type.is_dir() {
+            Some(relative_path)
+        } else {
+            relative_path.parent()
+        };
+        if dirs.is_some_and(|d| {
+            d.components()
+                .any(|c| skipped_dir(&c.as_os_str().to_string_lossy()))
+        }) {
+            continue;
+        }
 
-        if entry.header().entry_type().is_dir() {
+        extracted_entries += 1;
+        if extracted_entries > limits.entries {
+            return Err(format!("Archive has more than {} files", limits.entries));
+        }
+
+        let out_path = dest.join(relative_path);
+
+        if entry_type.is_dir() {
             std::fs::create_dir_all(&out_path)
                 .map_err(|e| format!("Failed to create dir {}: {e}", out_path.display()))?;
         } else {
@@ -74,44 +300,460 @@ pub(super) fn download_github_course(
                 std::fs::create_dir_all(parent)
                     .map_err(|e| format!("Failed to create dir for {}: {e}", out_path.display()))?;
             }
-            let mut buf = Vec::new();
-            entry
-                .read_to_end(&mut buf)
-                .map_err(|e| format!("Failed to read {}: {e}", relative))?;
-            std::fs::write(&out_path, &buf)
+            let mut file = std::fs::File::create(&out_path)
                 .map_err(|e| format!("Failed to write {}: {e}", out_path.display()))?;
+            // One byte over the budget is enough to know it was exceeded.
+            let budget = limits.bytes - extracted_bytes;
+            let written = std::io::copy(&mut (&mut entry).take(budget + 1), &mut file)
+                .map_err(|e| format!("Failed to extract {relative}: {e}"))?;
+            extracted_bytes += written;
+            if extracted_bytes > limits.bytes {
+                return Err(format!(
+                    "Archive extracts to more than {} MiB",
+                    limits.bytes / (1024 * 1024)
+                ));
+            }
+        }
+
+        if extracted_entries.is_multiple_of(REPORT_EVERY_FILES) {
+            (transfer.on_event)(ImportEvent::Extracted {
+                files: extracted_entries,
+            });
         }
     }
 
+    (transfer.on_event)(ImportEvent::Extracted {
+        files: extracted_entries,
+    });
     Ok(())
 }
 
+/// File index an HTTP course can publish next to its manifest, listing
+/// every file so lab scaffolds and multi-file lessons come along too.
+const HTTP_INDEX: &str = "handhold-files.json";
+
+#[derive(Deserialize)]
+struct FileIndex {
+    files: Vec<IndexedFile>,
+}
+
+#[derive(Deserialize)]
+struct IndexedFile {
+    path: String,
+    /// Hex SHA-256, checked after download when present.
+    #[serde(default)]
+    sha256: Option<String>,
+}
+
+/// Download an HTTP-hosted course. In order of preference: the manifest's
+/// `archive`, the `handhold-files.json` index, or each step path on its own.
 pub(super) fn download_http_course(
     base_url: &str,
     manifest_text: &str,
     manifest: &Manifest,
     dest: &Path,
+    transfer: &Transfer,
 ) -> Result<(), String> {
+    if let Some(archive) = &manifest.archive {
+        let archive_url = if archive.starts_with("https://") || archive.starts_with("http://") {
+            archive.clone()
+        } else {
+            format!("{base_url}{archive}")
+        };
+        download_archive_course(&archive_url, None, "", dest, transfer)?;
+    } else {
+        let paths = match fetch_file_index(base_url)? {
+            Some(index) => index.files,
+            None => manifest
+                .steps
+                .iter()
+                .map(|step| &step.path)
+                .chain(
+                    manifest
+                        .locales
+                        .values()
+                        .flat_map(|locale| locale.steps.values())
+                        .filter_map(|step| step.path.as_ref()),
+                )
+                .map(|path| IndexedFile {
+                    path: path.clone(),
+                    sha256: None,
+                })
+                .collect(),
+        };
+        download_files(base_url, &paths, dest, transfer)?;
+    }
+
+    // Written last so the installed manifest is always the one import
+    // validated, even if the archive or index carried an older copy.
     std::fs::write(dest.join("handhold.yaml"), manifest_text)
-        .map_err(|e| format!("Failed to write manifest: {e}"))?;
-
-    for step in &manifest.steps {
-        let file_url = format!("{}{}", base_url, step.path);
-        let resp = reqwest::blocking::get(&file_url)
-            .and_then(reqwest::blocking::Response::error_for_status)
-            .map_err(|e| format!("Failed to download {}: {e}", step.path))?;
-        let bytes = resp
-            .bytes()
-            .map_err(|e| format!("Failed to read {}: {e}", step.path))?;
-
-        let out_path = dest.join(&step.path);
+        .map_err(|e| format!("Failed to write manifest: {e}"))
+}
+
+/// `Ok(None)` when the course doesn't publish an index.
+fn fetch_file_index(base_url: &str) -> Result<Option<FileIndex>, String> {
+    let index_url = format!("{base_url}{HTTP_INDEX}");
+    let resp =
+        get_blocking(&index_url).map_err(|e| format!("Failed to download {HTTP_INDEX}: {e}"))?;
+    if resp.status().as_u16() == 404 {
+        return Ok(None);
+    }
+    let text = resp
+        .error_for_status()
+        .and_then(reqwest::blocking::Response::text)
+        .map_err(|e| format!("Failed to download {HTTP_INDEX}: {e}"))?;
+    serde_json::from_str(&text)
+        .map(Some)
+        .map_err(|e| format!("Invalid {HTTP_INDEX}: {e}"))
+}
+
+/// Hashes what passes through, so a download is checked as it streams to disk.
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn download_files(
+    base_url: &str,
+    files: &[IndexedFile],
+    dest: &Path,
+    transfer: &Transfer,
+) -> Result<(), String> {
+    if files.len() > MAX_EXTRACTED_ENTRIES {
+        return Err(format!(
+            "Course lists more than {MAX_EXTRACTED_ENTRIES} files"
+        ));
+    }
+
+    let mut downloaded: u64 = 0;
+    for (i, file) in files.iter().enumerate() {
+        transfer.check_cancelled()?;
+        if file.path == "handhold.yaml" {
+            continue;
+        }
+        let Some(relative) = safe_relative(&file.path) else {
+            return Err(format!(
+                "File path escapes the course directory: {}",
+                file.path
+            ));
+        };
+
+        let file_url = format!("{}{}", base_url, file.path);
+        let resp = get_blocking(&file_url)
+            .and_then(|r| r.error_for_status().map_err(|e| e.to_string()))
+            .map_err(|e| format!("Failed to download {}: {e}", file.path))?;
+
+        let out_path = dest.join(relative);
         if let Some(parent) = out_path.parent() {
             std::fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create dir for {}: {e}", step.path))?;
+                .map_err(|e| format!("Failed to create dir for {}: {e}", file.path))?;
+        }
+        let mut out = HashingWriter {
+            inner: File::create(&out_path)
+                .map_err(|e| format!("Failed to write {}: {e}", file.path))?,
+            hasher: Sha256::new(),
+        };
+        // Streamed to disk with the budget applied as it arrives, so an
+        // endless response can't fill memory first. One byte over is
+        // enough to know it was exceeded.
+        let budget = MAX_EXTRACTED_BYTES - downloaded;
+        let written = std::io::copy(&mut resp.take(budget + 1), &mut out)
+            .map_err(|e| format!("Failed to download {}: {e}", file.path))?;
+        downloaded += written;
+        if downloaded > MAX_EXTRACTED_BYTES {
+            return Err(format!(
+                "Course is larger than {} MiB",
+                MAX_EXTRACTED_BYTES / (1024 * 1024)
+            ));
         }
-        std::fs::write(&out_path, &bytes)
-            .map_err(|e| format!("Failed to write {}: {e}", step.path))?;
+
+        if let Some(expected) = &file.sha256 {
+            let actual = format!("{:x}", out.hasher.finalize());
+            if !actual.eq_ignore_ascii_case(expected) {
+                return Err(format!("Checksum mismatch for {}", file.path));
+            }
+        }
+
+        (transfer.on_event)(ImportEvent::Downloaded {
+            bytes: downloaded,
+            total: None,
+        });
+        (transfer.on_event)(ImportEvent::Extracted { files: i + 1 });
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use tar::{Builder, EntryType, Header};
+
+    const MIB: u64 = 1024 * 1024;
+
+    const SMALL: Limits = Limits {
+        bytes: MIB,
+        entries: 10,
+        decompressed: 4 * MIB,
+    };
+
+    /// Appends an entry with its name written straight into the header, since
+    /// `Header::set_path` refuses the `..` and absolute names under test.
+    fn append(
+        builder: &mut Builder<Vec<u8>>,
+        name: &str,
+        entry_type: EntryType,
+        link: Option<&str>,
+        data: &[u8],
+    ) {
+        let mut header = Header::new_old();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_entry_type(entry_type);
+        header.set_mode(0o644);
+        header.set_size(data.len() as u64);
+        if let Some(link) = link {
+            header.set_link_name_literal(link).unwrap();
+        }
+        header.set_cksum();
+        builder.append(&header, data).unwrap();
+    }
+
+    fn tarball(build: impl FnOnce(&mut Builder<Vec<u8>>)) -> Vec<u8> {
+        let mut builder = Builder::new(Vec::new());
+        build(&mut builder);
+        let tar = builder.into_inner().unwrap();
+        let mut gz = GzEncoder::new(Vec::new(), Compression::best());
+        gz.write_all(&tar).unwrap();
+        gz.finish().unwrap()
+    }
+
+    /// A fresh directory per test holding only `dest`, so anything written
+    /// outside `dest` shows up as a sibling.
+    fn scratch(name: &str) -> (PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("handhold-tar-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let dest = root.join("dest");
+        std::fs::create_dir_all(&dest).unwrap();
+        (root, dest)
+    }
+
+    fn extract(archive: &[u8], subpath: &str, dest: &Path, limits: &Limits) -> Result<(), String> {
+        extract_tarball_within(archive, subpath, dest, &Transfer::silent(), limits)
+    }
+
+    fn assert_contained(root: &Path) {
+        let entries: Vec<_> = std::fs::read_dir(root)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, ["dest"], "wrote outside dest");
+    }
+
+    #[test]
+    fn extracts_the_subpath() {
+        let (root, dest) = scratch("ok");
+        let archive = tarball(|b| {
+            append(b, "repo-main/", EntryType::Directory, None, b"");
+            append(
+                b,
+                "repo-main/README.md",
+                EntryType::Regular,
+                None,
+                b"readme",
+            );
+            append(
+                b,
+                "repo-main/course/handhold.yaml",
+                EntryType::Regular,
+                None,
+                b"title: x",
+            );
+        });
+        extract(&archive, "course", &dest, &SMALL).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dest.join("handhold.yaml")).unwrap(),
+            "title: x"
+        );
+        assert!(!dest.join("README.md").exists());
+        assert_contained(&root);
+    }
+
+    #[test]
+    fn skips_vcs_and_build_dirs() {
+        let (root, dest) = scratch("skipped");
+        let archive = tarball(|b| {
+            append(b, "repo-main/.git/", EntryType::Directory, None, b"");
+            append(b, "repo-main/.git/HEAD", EntryType::Regular, None, b"ref");
+            append(
+                b,
+                "repo-main/lab/node_modules/x.js",
+                EntryType::Regular,
+                None,
+                b"x",
+            );
+            append(b, "repo-main/lab/build", EntryType::Regular, None, b"file");
+        });
+        extract(&archive, "", &dest, &SMALL).unwrap();
+        assert!(!dest.join(".git").exists());
+        assert!(!dest.join("lab/node_modules").exists());
+        // Only directories are skipped; a file may share their names.
+        assert!(dest.join("lab/build").is_file());
+        assert_contained(&root);
+    }
+
+    #[test]
+    fn rejects_parent_dir_entries() {
+        let (root, dest) = scratch("parent");
+        let archive = tarball(|b| {
+            append(
+                b,
+                "repo-main/../../evil",
+                EntryType::Regular,
+                None,
+                b"pwned",
+            );
+        });
+        assert_eq!(
+            extract(&archive, "", &dest, &SMALL),
+            Err("Archive entry escapes the course directory: repo-main/../../evil".to_string())
+        );
+        assert_contained(&root);
+    }
+
+    #[test]
+    fn rejects_absolute_entries() {
+        let (root, dest) = scratch("absolute");
+        let target = root.join("evil");
+        let name = target.to_string_lossy().into_owned();
+        let archive = tarball(|b| append(b, &name, EntryType::Regular, None, b"pwned"));
+        assert_eq!(
+            extract(&archive, "", &dest, &SMALL),
+            Err(format!(
+                "Archive entry escapes the course directory: {name}"
+            ))
+        );
+        assert!(!target.exists());
+        assert_contained(&root);
+    }
+
+    #[test]
+    fn skips_symlinks() {
+        let (root, dest) = scratch("symlink");
+        let outside = root.to_string_lossy().into_owned();
+        // The classic: plant a link to outside, then write through it.
+        let archive = tarball(|b| {
+            append(b, "repo-main/link", EntryType::Symlink, Some(&outside), b"");
+            append(b, "repo-main/link/evil", EntryType::Regular, None, b"pwned");
+        });
+        extract(&archive, "", &dest, &SMALL).unwrap();
+        let link = std::fs::symlink_metadata(dest.join("link")).unwrap();
+        assert!(link.is_dir(), "symlink was created");
+        assert_eq!(
+            std::fs::read_to_string(dest.join("link/evil")).unwrap(),
+            "pwned"
+        );
+        assert_contained(&root);
+    }
+
+    #[test]
+    fn skips_hardlinks() {
+        let (root, dest) = scratch("hardlink");
+        std::fs::write(root.join("secret"), "secret").unwrap();
+        let secret = root.join("secret").to_string_lossy().into_owned();
+        let archive = tarball(|b| {
+            append(b, "repo-main/hard", EntryType::Link, Some(&secret), b"");
+        });
+        extract(&archive, "", &dest, &SMALL).unwrap();
+        assert!(!dest.join("hard").exists());
+        std::fs::remove_file(root.join("secret")).unwrap();
+        assert_contained(&root);
+    }
+
+    #[test]
+    fn rejects_archives_over_the_size_limit() {
+        let (root, dest) = scratch("bytes");
+        let half = vec![0u8; (MIB / 2 + 1) as usize];
+        let archive = tarball(|b| {
+            append(b, "repo-main/a", EntryType::Regular, None, &half);
+            append(b, "repo-main/b", EntryType::Regular, None, &half);
+        });
+        assert_eq!(
+            extract(&archive, "", &dest, &SMALL),
+            Err("Archive extracts to more than 1 MiB".to_string())
+        );
+        let written = std::fs::metadata(dest.join("b")).unwrap().len();
+        assert!(written <= MIB / 2, "kept writing past the limit");
+        assert_contained(&root);
+    }
+
+    #[test]
+    fn rejects_archives_over_the_entry_limit() {
+        let (root, dest) = scratch("entries");
+        let archive = tarball(|b| {
+            for i in 0..=SMALL.entries {
+                append(
+                    b,
+                    &format!("repo-main/f{i}"),
+                    EntryType::Regular,
+                    None,
+                    b"x",
+                );
+            }
+        });
+        assert_eq!(
+            extract(&archive, "", &dest, &SMALL),
+            Err("Archive has more than 10 files".to_string())
+        );
+        assert!(!dest.join(format!("f{}", SMALL.entries)).exists());
+        assert_contained(&root);
+    }
+
+    #[test]
+    fn rejects_gzip_bombs_outside_the_subpath() {
+        let (root, dest) = scratch("bomb");
+        // Compresses to a few KiB. Skipped entries still have to be inflated.
+        let zeros = vec![0u8; (8 * MIB) as usize];
+        let archive = tarball(|b| {
+            append(
+                b,
+                "repo-main/elsewhere/bomb",
+                EntryType::Regular,
+                None,
+                &zeros,
+            );
+            append(
+                b,
+                "repo-main/course/handhold.yaml",
+                EntryType::Regular,
+                None,
+                b"title: x",
+            );
+        });
+        assert!(archive.len() < (64 * 1024) as usize);
+        let limits = Limits {
+            bytes: 16 * MIB,
+            ..SMALL
+        };
+        assert_eq!(
+            extract(&archive, "course", &dest, &limits),
+            Err("Tarball entry error: archive decompresses to more than 4 MiB".to_string())
+        );
+        assert_contained(&root);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::db::Db;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::HashSet;
+use std::path::Path;
+use tauri::State;
+
+use super::queries::read_course_row;
+use super::schema::load_manifest;
+use super::source::{parse_source_url, source_id};
+use super::types::{CourseUnlocks, LockReason, Manifest, StepLock};
+
+/// Reads a course's manifest from its install directory. Takes the lock
+/// only for the path lookup, so callers must not hold it.
+pub(super) fn installed_manifest(db: &Db, id: &str) -> Result<Manifest, String> {
+    let local_path: String =
+        db.0.lock()
+            .query_row(
+                "SELECT local_path FROM course WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Course not found: {e}"))?;
+    let text = std::fs::read_to_string(Path::new(&local_path).join("handhold.yaml"))
+        .map_err(|e| format!("Failed to read manifest: {e}"))?;
+    load_manifest(&text).map_err(|e| format!("Failed to parse manifest: {e}"))
+}
+
+/// Index of the step a step's `requires:` entry names — by id, then by path.
+pub(super) fn resolve_step(manifest: &Manifest, reference: &str) -> Option<usize> {
+    let steps = &manifest.steps;
+    steps
+        .iter()
+        .position(|s| s.id == reference)
+        .or_else(|| steps.iter().position(|s| s.path == reference))
+}
+
+fn completed_step_ids(conn: &Connection, id: &str) -> Result<HashSet<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT step_id FROM step_completion WHERE course_id = ?1")
+        .map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map(params![id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<HashSet<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(ids)
+}
+
+/// Course-level `requires:` entries that aren't finished. A course counts as
+/// finished once every required (non-optional) step is complete.
+fn course_locks(conn: &Connection, manifest: &Manifest) -> Result<Vec<LockReason>, String> {
+    let forges = crate::settings::current().forges;
+    let mut reasons = Vec::new();
+    for source_url in &manifest.requires {
+        let required_id = parse_source_url(source_url, &forges).map(|s| source_id(&s));
+        let found: Option<String> = conn
+            .query_row(
+                "SELECT id FROM course WHERE id = ?1 OR source_url = ?2",
+                params![required_id, source_url],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some(found) = found else {
+            reasons.push(LockReason::CourseMissing {
+                source_url: source_url.clone(),
+            });
+            continue;
+        };
+        let course = read_course_row(conn, &found)?;
+        if course.completed_steps < course.required_steps {
+            reasons.push(LockReason::Course {
+                source_url: source_url.clone(),
+                title: course.title,
+            });
+        }
+    }
+    Ok(reasons)
+}
+
+fn step_locks(manifest: &Manifest, completed: &HashSet<String>, index: usize) -> Vec<LockReason> {
+    let Some(step) = manifest.steps.get(index) else {
+        return Vec::new();
+    };
+    step.requires
+        .iter()
+        .filter_map(|reference| resolve_step(manifest, reference))
+        .filter(|&i| i != index && !completed.contains(&manifest.steps[i].id))
+        .map(|i| LockReason::Step {
+            step_index: i,
+            title: manifest.steps[i].title.clone(),
+        })
+        .collect()
+}
+
+pub(super) fn course_gating(
+    conn: &Connection,
+    id: &str,
+    manifest: &Manifest,
+) -> Result<CourseUnlocks, String> {
+    let completed = completed_step_ids(conn, id)?;
+    let steps = (0..manifest.steps.len())
+        .filter_map(|index| {
+            let reasons = step_locks(manifest, &completed, index);
+            (!reasons.is_empty()).then_some(StepLock {
+                step_index: index,
+                reasons,
+            })
+        })
+        .collect();
+    Ok(CourseUnlocks {
+        free_navigation: manifest.free_navigation,
+        course: course_locks(conn, manifest)?,
+        steps,
+    })
+}
+
+fn describe(reason: &LockReason) -> String {
+    match reason {
+        LockReason::Step { step_index, title } => {
+            format!("finish step {} ({title})", step_index + 1)
+        }
+        LockReason::Course { title, .. } => format!("finish {title}"),
+        LockReason::CourseMissing { source_url } => format!("install and finish {source_url}"),
+    }
+}
+
+/// Errors when `step_index` has unmet prerequisites and the course doesn't
+/// allow free navigation.
+pub(super) fn check_unlocked(
+    conn: &Connection,
+    id: &str,
+    manifest: &Manifest,
+    step_index: i64,
+) -> Result<(), String> {
+    if manifest.free_navigation {
+        return Ok(());
+    }
+    let completed = completed_step_ids(conn, id)?;
+    let mut reasons = course_locks(conn, manifest)?;
+    if let Ok(index) = usize::try_from(step_index) {
+        reasons.extend(step_locks(manifest, &completed, index));
+    }
+    if reasons.is_empty() {
+        return Ok(());
+    }
+    let needed: Vec<String> = reasons.iter().map(describe).collect();
+    Err(format!(
+        "Step {} is locked: {}",
+        step_index + 1,
+        needed.join(", ")
+    ))
+}
+
+/// Locked steps of a course and why, evaluated against current progress.
+#[tauri::command]
+pub async fn course_unlocks(db: State<'_, Db>, id: String) -> Result<CourseUnlocks, String> {
+    let manifest = installed_manifest(&db, &id)?;
+    let conn = db.0.lock();
+    course_gating(&conn, &id, &manifest)
+}
@@ -1,19 +1,98 @@
 use crate::db::Db;
 use crate::paths::courses_dir;
+use parking_lot::Mutex;
 use rusqlite::params;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tauri::State;
+use tauri::ipc::Channel;
 
-use super::download::{download_github_course, download_http_course};
+use super::credentials::{self, credential_host, has_credential};
+use super::download::{Transfer, download_archive_course, download_http_course};
+use super::integrity::verify_course;
 use super::now_ms;
 use super::queries::read_course_row;
+use super::schema::{ManifestError, SCHEMA_VERSION, load_manifest};
 use super::source::{
-    CourseSource, canonical_source_url, manifest_url, parse_source_url, source_id,
+    CourseSource, archive_url, canonical_source_url, commit_api, default_branch_api, manifest_url,
+    parse_source_url, private_archive_url, private_manifest_url, set_branch, source_id, subpath,
 };
-use super::types::{ImportResult, Manifest};
+use super::types::{CourseRecord, ImportEvent, ImportResult, Manifest};
+use super::validate::{has_errors, validate_course};
 
+/// Cancel flags for in-flight `course_import` calls, keyed by the URL the
+/// frontend passed in — it has no course id until the import finishes.
+#[derive(Default)]
+pub struct ActiveImports(Mutex<HashMap<String, Arc<AtomicBool>>>);
+
+impl ActiveImports {
+    pub fn new() -> Self {
+        Self(Mutex::new(HashMap::new()))
+    }
+
+    fn begin(&self, source_url: &str) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        self.0.lock().insert(source_url.to_string(), flag.clone());
+        flag
+    }
+
+    fn finish(&self, source_url: &str) {
+        self.0.lock().remove(source_url);
+    }
+}
+
+/// Imports a course from a forge or HTTP URL, streaming phases over `on_event`.
+#[tauri::command]
+pub async fn course_import(
+    db: State<'_, Db>,
+    imports: State<'_, ActiveImports>,
+    source_url: String,
+    on_event: Channel<ImportEvent>,
+) -> Result<ImportResult, String> {
+    let emit = |event: ImportEvent| {
+        let _ = on_event.send(event);
+    };
+    run_import(&db, &imports, &source_url, &source_url, &emit).await
+}
+
+/// Imports `source_url` with a cancel flag registered under `cancel_key`.
+pub(super) async fn run_import(
+    db: &Db,
+    imports: &ActiveImports,
+    cancel_key: &str,
+    source_url: &str,
+    on_event: &(dyn Fn(ImportEvent) + Sync),
+) -> Result<ImportResult, String> {
+    let cancelled = imports.begin(cancel_key);
+    let transfer = Transfer {
+        on_event,
+        cancelled: &cancelled,
+    };
+    let result = import_from_url(db, source_url, &transfer).await;
+    imports.finish(cancel_key);
+    result
+}
+
+/// Stops a running `course_import` for `source_url` and discards its partial download.
 #[tauri::command]
-pub async fn course_import(db: State<'_, Db>, source_url: String) -> Result<ImportResult, String> {
-    let Some(source) = parse_source_url(&source_url) else {
+pub async fn course_import_cancel(
+    imports: State<'_, ActiveImports>,
+    source_url: String,
+) -> Result<(), String> {
+    if let Some(flag) = imports.0.lock().get(&source_url) {
+        flag.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+async fn import_from_url(
+    db: &Db,
+    source_url: &str,
+    transfer: &Transfer<'_>,
+) -> Result<ImportResult, String> {
+    let forges = crate::settings::current().forges;
+    let Some(mut source) = parse_source_url(source_url, &forges) else {
         return Ok(ImportResult::InvalidUrl);
     };
 
@@ -33,35 +112,18 @@ pub async fn course_import(db: State<'_, Db>, source_url: String) -> Result<Impo
         }
     }
 
-    let raw_url = manifest_url(&source);
-    let manifest_text = match reqwest::get(&raw_url).await {
-        Ok(resp) if resp.status().is_success() => match resp.text().await {
-            Ok(text) => text,
-            Err(e) => {
-                return Ok(ImportResult::BadManifest {
-                    reason: e.to_string(),
-                });
-            }
-        },
-        Ok(resp) if resp.status().as_u16() == 404 => return Ok(ImportResult::NoManifest),
-        Ok(_) => return Ok(ImportResult::NotFound),
-        Err(_) => return Ok(ImportResult::NotFound),
+    (transfer.on_event)(ImportEvent::Resolving);
+    let fetched = match fetch_manifest(&mut source).await {
+        Ok(fetched) => fetched,
+        Err(e) => return Ok(e.into()),
     };
-
-    let manifest: Manifest = match serde_yml::from_str(&manifest_text) {
-        Ok(m) => m,
-        Err(e) => {
-            return Ok(ImportResult::BadManifest {
-                reason: e.to_string(),
-            });
-        }
-    };
-
-    if manifest.steps.is_empty() {
-        return Ok(ImportResult::BadManifest {
-            reason: "Manifest has no steps".to_string(),
-        });
+    if transfer.is_cancelled() {
+        return Ok(ImportResult::Cancelled);
     }
+    (transfer.on_event)(ImportEvent::ManifestFetched {
+        title: fetched.manifest.title.clone(),
+        step_count: fetched.manifest.steps.len(),
+    });
 
     let dest = courses_dir().join(&id);
     if dest.exists() {
@@ -71,53 +133,293 @@ pub async fn course_import(db: State<'_, Db>, source_url: String) -> Result<Impo
     std::fs::create_dir_all(&dest)
         .map_err(|e| format!("Failed to create course directory: {e}"))?;
 
-    match &source {
-        CourseSource::GitHub {
-            owner,
-            repo,
-            branch,
-            path,
-        } => {
-            if let Err(e) = download_github_course(owner, repo, branch, path, &dest) {
-                let _ = std::fs::remove_dir_all(&dest);
-                return Ok(ImportResult::DownloadFailed { reason: e });
-            }
-        }
-        CourseSource::Http { base_url, .. } => {
-            if let Err(e) = download_http_course(base_url, &manifest_text, &manifest, &dest) {
-                let _ = std::fs::remove_dir_all(&dest);
-                return Ok(ImportResult::DownloadFailed { reason: e });
-            }
-        }
+    let downloaded = download_source(
+        &source,
+        fetched.revision.as_deref(),
+        &fetched.text,
+        &fetched.manifest,
+        &dest,
+        transfer,
+    );
+    // The flag wins over whatever error the interrupted download produced.
+    if transfer.is_cancelled() {
+        let _ = std::fs::remove_dir_all(&dest);
+        return Ok(ImportResult::Cancelled);
+    }
+    if let Err(e) = downloaded {
+        let _ = std::fs::remove_dir_all(&dest);
+        return Ok(ImportResult::DownloadFailed { reason: e });
+    }
+    if let Err(e) = verify_course(&dest) {
+        let _ = std::fs::remove_dir_all(&dest);
+        return Ok(e.into());
+    }
+    let diagnostics = validate_course(&dest);
+    if has_errors(&diagnostics) {
+        let _ = std::fs::remove_dir_all(&dest);
+        return Ok(ImportResult::Invalid { diagnostics });
     }
 
+    (transfer.on_event)(ImportEvent::Registering);
     let url_for_db = canonical_source_url(&source);
     let local_path = dest.to_string_lossy().to_string();
-    let now = now_ms();
+
+    let mut conn = db.0.lock();
+    let course = register_course(
+        &mut conn,
+        &id,
+        &url_for_db,
+        &local_path,
+        &fetched.manifest,
+        fetched.revision.as_deref(),
+    )?;
+    Ok(ImportResult::Ok { course })
+}
+
+/// Why a remote manifest couldn't be fetched. Each command maps this
+/// onto its own result type.
+pub(super) enum FetchError {
+    NotFound,
+    NoManifest,
+    BadManifest(String),
+    /// The host rejected our credentials, or wants some. Carries the host
+    /// to add a credential for.
+    Unauthorized(String),
+    /// The manifest's `schemaVersion` is newer than this app reads.
+    TooNew(u32),
+    /// No response at all: DNS, TLS, proxy or connection failure.
+    Network(String),
+    /// The forge wouldn't say which commit the ref points at (rate limit,
+    /// API disabled), so the import can't be pinned.
+    Unpinned(String),
+}
+
+impl From<ManifestError> for FetchError {
+    fn from(e: ManifestError) -> Self {
+        match e {
+            ManifestError::TooNew(version) => FetchError::TooNew(version),
+            invalid => FetchError::BadManifest(invalid.to_string()),
+        }
+    }
+}
+
+impl From<ManifestError> for ImportResult {
+    fn from(e: ManifestError) -> Self {
+        FetchError::from(e).into()
+    }
+}
+
+impl From<FetchError> for ImportResult {
+    fn from(e: FetchError) -> Self {
+        match e {
+            FetchError::NotFound => ImportResult::NotFound,
+            FetchError::NoManifest => ImportResult::NoManifest,
+            FetchError::BadManifest(reason) => ImportResult::BadManifest { reason },
+            FetchError::Unauthorized(host) => ImportResult::Unauthorized { host },
+            FetchError::TooNew(required) => ImportResult::NeedsNewerApp {
+                required,
+                supported: SCHEMA_VERSION,
+            },
+            FetchError::Network(reason) => ImportResult::NetworkError { reason },
+            FetchError::Unpinned(reason) => ImportResult::Unpinned { reason },
+        }
+    }
+}
+
+pub(super) struct FetchedManifest {
+    pub text: String,
+    pub manifest: Manifest,
+    /// Commit SHA the manifest was read at. `None` only for plain HTTP.
+    pub revision: Option<String>,
+}
+
+/// Resolves `source` to an immutable commit, then fetches and parses its
+/// manifest at that commit. The archive download that follows reads the
+/// same commit, so the installed files match the manifest validated here.
+pub(super) async fn fetch_manifest(
+    source: &mut CourseSource,
+) -> Result<FetchedManifest, FetchError> {
+    if let Some(api_url) = default_branch_api(source) {
+        let branch = fetch_default_branch(&api_url).await?;
+        set_branch(source, branch);
+    }
+
+    let revision = match commit_api(source) {
+        Some((api_url, pointer)) => resolve_revision(&api_url, pointer).await?,
+        None => None,
+    };
+    if let Some(sha) = &revision {
+        set_branch(source, sha.clone());
+    }
+
+    let raw_url = if has_credential(&manifest_url(source)) {
+        private_manifest_url(source)
+    } else {
+        manifest_url(source)
+    };
+    let manifest_text = match credentials::get(&raw_url).await {
+        Ok(resp) if resp.status().is_success() => resp
+            .text()
+            .await
+            .map_err(|e| FetchError::BadManifest(e.to_string()))?,
+        Ok(resp) if resp.status().as_u16() == 404 => return Err(FetchError::NoManifest),
+        Ok(resp) if is_unauthorized(resp.status(), &raw_url) => {
+            return Err(unauthorized(&raw_url));
+        }
+        Ok(_) => return Err(FetchError::NotFound),
+        Err(e) => return Err(FetchError::Network(e)),
+    };
+
+    let manifest = parse_manifest(&manifest_text)?;
+    Ok(FetchedManifest {
+        text: manifest_text,
+        manifest,
+        revision,
+    })
+}
+
+/// Asks the forge which commit a ref points at. A 404 means the repo or
+/// ref doesn't exist. Any other failure (rate limits, API disabled) fails
+/// the fetch rather than installing whatever the ref points at by then.
+async fn resolve_revision(api_url: &str, pointer: &str) -> Result<Option<String>, FetchError> {
+    let resp = match credentials::get(api_url).await {
+        Ok(resp) if resp.status().as_u16() == 404 => return Err(FetchError::NotFound),
+        Ok(resp) if is_unauthorized(resp.status(), api_url) => return Err(unauthorized(api_url)),
+        Ok(resp) if resp.status().is_success() => resp,
+        Ok(resp) => {
+            return Err(FetchError::Unpinned(format!(
+                "revision lookup returned {}",
+                resp.status()
+            )));
+        }
+        Err(e) => return Err(FetchError::Network(e)),
+    };
+    let body = resp
+        .text()
+        .await
+        .map_err(|e| FetchError::Unpinned(e.to_string()))?;
+    let json: serde_json::Value =
+        serde_json::from_str(&body).map_err(|e| FetchError::Unpinned(e.to_string()))?;
+    json.pointer(pointer)
+        .and_then(|v| v.as_str())
+        .map(|sha| Some(sha.to_string()))
+        .ok_or_else(|| FetchError::Unpinned("revision lookup had no commit".to_string()))
+}
+
+/// Fills `dest` with the course content: a tarball for forges, file by file for HTTP.
+pub(super) fn download_source(
+    source: &CourseSource,
+    revision: Option<&str>,
+    manifest_text: &str,
+    manifest: &Manifest,
+    dest: &std::path::Path,
+    transfer: &Transfer,
+) -> Result<(), String> {
+    let tarball_url = match archive_url(source) {
+        Some(url) if has_credential(&url) => private_archive_url(source),
+        other => other,
+    };
+    match (source, tarball_url) {
+        (CourseSource::Http { base_url, .. }, _) => {
+            download_http_course(base_url, manifest_text, manifest, dest, transfer)
+        }
+        (_, Some(tarball_url)) => {
+            download_archive_course(&tarball_url, revision, subpath(source), dest, transfer)
+        }
+        (_, None) => Err("No archive URL for source".to_string()),
+    }
+}
+
+/// Parses `handhold.yaml` and rejects manifests a course can't be built from.
+pub(super) fn parse_manifest(text: &str) -> Result<Manifest, ManifestError> {
+    let manifest = load_manifest(text)?;
+    if manifest.steps.is_empty() {
+        return Err(ManifestError::Invalid {
+            reason: "Manifest has no steps".to_string(),
+            line: None,
+        });
+    }
+    Ok(manifest)
+}
+
+/// Inserts the course, its tags and its steps in one transaction, then reads
+/// back the record the browser shows.
+pub(super) fn register_course(
+    conn: &mut rusqlite::Connection,
+    id: &str,
+    source_url: &str,
+    local_path: &str,
+    manifest: &Manifest,
+    revision: Option<&str>,
+) -> Result<CourseRecord, String> {
     let step_count = manifest.steps.len() as i64;
-    let title = manifest.title.clone();
-    let description = manifest.description.clone();
-    let tags = manifest.tags.clone();
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin registration: {e}"))?;
+    tx.execute(
+        "INSERT INTO course (id, source_url, local_path, title, description, step_count, added_at, revision)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![id, source_url, local_path, &manifest.title, &manifest.description, step_count, now_ms(), revision],
+    )
+    .map_err(|e| format!("Failed to insert course: {e}"))?;
 
-    {
-        let conn = db.0.lock();
-        conn.execute(
-            "INSERT INTO course (id, source_url, local_path, title, description, step_count, added_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![&id, &url_for_db, &local_path, &title, &description, step_count, now],
+    for tag in &manifest.tags {
+        tx.execute(
+            "INSERT INTO tag (course_id, name) VALUES (?1, ?2)",
+            params![id, tag],
         )
-        .map_err(|e| format!("Failed to insert course: {e}"))?;
+        .map_err(|e| format!("Failed to insert tag: {e}"))?;
+    }
+    store_steps(&tx, id, manifest)?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit registration: {e}"))?;
 
-        for tag in &tags {
-            conn.execute(
-                "INSERT INTO tag (course_id, name) VALUES (?1, ?2)",
-                params![&id, tag],
-            )
-            .map_err(|e| format!("Failed to insert tag: {e}"))?;
-        }
+    read_course_row(conn, id)
+}
+
+/// Replaces the course's step index → id map with the manifest's. Progress
+/// is keyed by step id; this map is how index-based commands find it.
+pub(super) fn store_steps(
+    conn: &rusqlite::Connection,
+    id: &str,
+    manifest: &Manifest,
+) -> Result<(), String> {
+    conn.execute("DELETE FROM course_step WHERE course_id = ?1", params![id])
+        .map_err(|e| format!("Failed to clear steps: {e}"))?;
+    for (index, step) in manifest.steps.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO course_step (course_id, step_index, step_id, optional)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![id, index as i64, &step.id, step.optional],
+        )
+        .map_err(|e| format!("Failed to insert step: {e}"))?;
     }
+    Ok(())
+}
 
-    let conn = db.0.lock();
-    let course = read_course_row(&conn, &id)?;
-    Ok(ImportResult::Ok { course })
+/// Looks up a Gitea/Forgejo repo's default branch via its REST API.
+async fn fetch_default_branch(api_url: &str) -> Result<String, FetchError> {
+    let resp = match credentials::get(api_url).await {
+        Ok(resp) if resp.status().is_success() => resp,
+        Ok(resp) if is_unauthorized(resp.status(), api_url) => return Err(unauthorized(api_url)),
+        Ok(_) => return Err(FetchError::NotFound),
+        Err(e) => return Err(FetchError::Network(e)),
+    };
+    let body = resp.text().await.map_err(|_| FetchError::NotFound)?;
+    let repo: serde_json::Value = serde_json::from_str(&body).map_err(|_| FetchError::NotFound)?;
+    repo.get("default_branch")
+        .and_then(|b| b.as_str())
+        .map(str::to_string)
+        .ok_or(FetchError::NotFound)
+}
+
+/// A 401 always means missing or rejected credentials. A 403 only does
+/// when we sent some — anonymous 403s are usually forge rate limits.
+fn is_unauthorized(status: reqwest::StatusCode, url: &str) -> bool {
+    status == reqwest::StatusCode::UNAUTHORIZED
+        || (status == reqwest::StatusCode::FORBIDDEN && has_credential(url))
+}
+
+fn unauthorized(url: &str) -> FetchError {
+    FetchError::Unauthorized(credential_host(url).unwrap_or_default())
 }
@@ -0,0 +1,365 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+use super::now_ms;
+use super::schema::load_manifest;
+use super::skipped_dir;
+use super::types::ImportResult;
+use crate::paths::trusted_publishers_path;
+
+/// Why a course failed verification. Each command maps this onto its own result type.
+pub(super) enum IntegrityError {
+    /// Checksum mismatch, unlisted or missing file, or a bad manifest signature
+    /// (reported as `handhold.yaml`).
+    File(String),
+    UntrustedPublisher(String),
+}
+
+impl From<IntegrityError> for ImportResult {
+    fn from(e: IntegrityError) -> Self {
+        match e {
+            IntegrityError::File(file) => ImportResult::IntegrityFailed { file },
+            IntegrityError::UntrustedPublisher(publisher) => {
+                ImportResult::UntrustedPublisher { publisher }
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedPublisher {
+    pub name: String,
+    /// Base64 ed25519 public key.
+    pub public_key: String,
+    pub added_at: i64,
+}
+
+fn read_keyring() -> Result<Vec<TrustedPublisher>, String> {
+    let path = trusted_publishers_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read trusted publishers: {e}"))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse trusted publishers: {e}"))
+}
+
+fn write_keyring(publishers: &[TrustedPublisher]) -> Result<(), String> {
+    let path = trusted_publishers_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create keyring dir: {e}"))?;
+    }
+    let content = serde_json::to_string_pretty(publishers)
+        .map_err(|e| format!("Failed to serialize trusted publishers: {e}"))?;
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write trusted publishers: {e}"))
+}
+
+fn decode_key(public_key: &str) -> Option<VerifyingKey> {
+    let bytes: [u8; 32] = STANDARD.decode(public_key.trim()).ok()?.try_into().ok()?;
+    VerifyingKey::from_bytes(&bytes).ok()
+}
+
+/// Rebuilds objects with keys in sorted order, whatever map type serde_json is built with.
+fn sort_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().map(|(k, v)| (k, sort_keys(v))).collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            serde_json::Value::Object(entries.into_iter().collect())
+        }
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.into_iter().map(sort_keys).collect())
+        }
+        other => other,
+    }
+}
+
+/// The bytes a publisher signs: the manifest as canonical JSON (sorted
+/// keys, no whitespace) with `integrity.signature` removed. Working on the
+/// generic YAML tree keeps signatures stable as the manifest model grows.
+fn signed_payload(manifest_text: &str) -> Option<Vec<u8>> {
+    let mut value: serde_json::Value = serde_yml::from_str(manifest_text).ok()?;
+    value
+        .get_mut("integrity")?
+        .as_object_mut()?
+        .remove("signature");
+    serde_json::to_vec(&sort_keys(value)).ok()
+}
+
+fn sha256_hex(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    Some(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Relative paths of every course file under `dir`, `/`-separated. A
+/// symlink fails verification: it could loop, or reach files outside the
+/// course that no checksum covers.
+fn collect_files(dir: &Path, prefix: &str, out: &mut Vec<String>) -> Result<(), IntegrityError> {
+    let unreadable = || {
+        IntegrityError::File(if prefix.is_empty() {
+            "handhold.yaml".to_string()
+        } else {
+            prefix.to_string()
+        })
+    };
+    for entry in std::fs::read_dir(dir).map_err(|_| unreadable())? {
+        let entry = entry.map_err(|_| unreadable())?;
+        let file_type = entry.file_type().map_err(|_| unreadable())?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if (file_type.is_dir() || file_type.is_symlink()) && skipped_dir(&name) {
+            continue;
+        }
+        let rel = if prefix.is_empty() {
+            name
+        } else {
+            format!("{prefix}/{name}")
+        };
+        if file_type.is_symlink() {
+            return Err(IntegrityError::File(rel));
+        }
+        if file_type.is_dir() {
+            collect_files(&entry.path(), &rel, out)?;
+        } else {
+            out.push(rel);
+        }
+    }
+    Ok(())
+}
+
+/// Checks a downloaded course against its manifest's `integrity` section.
+///
+/// Without that section the course is accepted as-is. With it, every file
+/// except `handhold.yaml` must be listed with a matching SHA-256, and a
+/// signature must verify against a key in the trusted-publisher keyring.
+pub(super) fn verify_course(dir: &Path) -> Result<(), IntegrityError> {
+    verify_course_with(dir, read_keyring)
+}
+
+fn verify_course_with(
+    dir: &Path,
+    keyring: impl FnOnce() -> Result<Vec<TrustedPublisher>, String>,
+) -> Result<(), IntegrityError> {
+    let manifest_file = || IntegrityError::File("handhold.yaml".to_string());
+    let manifest_text =
+        std::fs::read_to_string(dir.join("handhold.yaml")).map_err(|_| manifest_file())?;
+    let manifest = load_manifest(&manifest_text).map_err(|_| manifest_file())?;
+    let Some(integrity) = manifest.integrity else {
+        return Ok(());
+    };
+
+    let mut on_disk = Vec::new();
+    collect_files(dir, "", &mut on_disk)?;
+    on_disk.sort();
+    for file in on_disk.iter().filter(|f| *f != "handhold.yaml") {
+        let Some(expected) = integrity.files.get(file) else {
+            return Err(IntegrityError::File(file.clone()));
+        };
+        if sha256_hex(&dir.join(file)).is_none_or(|actual| !actual.eq_ignore_ascii_case(expected)) {
+            return Err(IntegrityError::File(file.clone()));
+        }
+    }
+    if let Some(missing) = integrity.files.keys().find(|f| !on_disk.contains(f)) {
+        return Err(IntegrityError::File(missing.clone()));
+    }
+
+    match (&integrity.publisher, &integrity.signature) {
+        (None, None) => Ok(()),
+        (Some(publisher), Some(signature)) => {
+            let keyring = keyring().map_err(|_| manifest_file())?;
+            let Some(key) = keyring
+                .iter()
+                .find(|p| &p.name == publisher)
+                .and_then(|p| decode_key(&p.public_key))
+            else {
+                return Err(IntegrityError::UntrustedPublisher(publisher.clone()));
+            };
+            let signature = STANDARD
+                .decode(signature.trim())
+                .ok()
+                .and_then(|bytes| Signature::from_slice(&bytes).ok())
+                .ok_or_else(manifest_file)?;
+            let payload = signed_payload(&manifest_text).ok_or_else(manifest_file)?;
+            key.verify_strict(&payload, &signature)
+                .map_err(|_| manifest_file())
+        }
+        // Half a signature block is treated as tampering, not as "unsigned".
+        _ => Err(manifest_file()),
+    }
+}
+
+#[tauri::command]
+pub async fn publisher_list() -> Result<Vec<TrustedPublisher>, String> {
+    read_keyring()
+}
+
+/// Adds or replaces a publisher key. `name` must match `integrity.publisher`
+/// in the manifests that publisher signs.
+#[tauri::command]
+pub async fn publisher_trust(name: String, public_key: String) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Publisher name is empty".to_string());
+    }
+    if decode_key(&public_key).is_none() {
+        return Err("Public key must be a base64 ed25519 key (32 bytes)".to_string());
+    }
+    let mut keyring = read_keyring()?;
+    keyring.retain(|p| p.name != name);
+    keyring.push(TrustedPublisher {
+        name,
+        public_key: public_key.trim().to_string(),
+        added_at: now_ms(),
+    });
+    write_keyring(&keyring)
+}
+
+#[tauri::command]
+pub async fn publisher_untrust(name: String) -> Result<(), String> {
+    let mut keyring = read_keyring()?;
+    keyring.retain(|p| p.name != name);
+    write_keyring(&keyring)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use std::path::PathBuf;
+
+    const LESSON: &str = "# Lesson";
+
+    fn publisher_key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    /// A fresh course directory per test holding `lesson.md`.
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("handhold-integrity-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("lesson.md"), LESSON).unwrap();
+        dir
+    }
+
+    /// Writes a manifest listing `files`, signed by `signer` as "acme" when given.
+    fn write_manifest(dir: &Path, files: &[(&str, &str)], signer: Option<&SigningKey>) {
+        let mut text = "title: T\ndescription: D\nsteps:\n  - kind: lesson\n    title: L\n    path: lesson.md\nintegrity:\n  files:\n".to_string();
+        for (file, content) in files {
+            let digest = format!("{:x}", Sha256::digest(content.as_bytes()));
+            text.push_str(&format!("    {file}: {digest}\n"));
+        }
+        if let Some(signer) = signer {
+            text.push_str("  publisher: acme\n  signature: SIGNATURE\n");
+            let payload = signed_payload(&text).unwrap();
+            let signature = STANDARD.encode(signer.sign(&payload).to_bytes());
+            text = text.replace("SIGNATURE", &signature);
+        }
+        std::fs::write(dir.join("handhold.yaml"), text).unwrap();
+    }
+
+    fn no_keys() -> Result<Vec<TrustedPublisher>, String> {
+        Ok(Vec::new())
+    }
+
+    fn trusting(key: &SigningKey) -> impl FnOnce() -> Result<Vec<TrustedPublisher>, String> {
+        let public_key = STANDARD.encode(key.verifying_key().to_bytes());
+        move || {
+            Ok(vec![TrustedPublisher {
+                name: "acme".to_string(),
+                public_key,
+                added_at: 0,
+            }])
+        }
+    }
+
+    fn failed_file(result: Result<(), IntegrityError>) -> String {
+        match result {
+            Err(IntegrityError::File(file)) => file,
+            Err(IntegrityError::UntrustedPublisher(p)) => panic!("untrusted publisher {p}"),
+            Ok(()) => panic!("verified"),
+        }
+    }
+
+    #[test]
+    fn accepts_a_signed_course() {
+        let dir = scratch("signed");
+        write_manifest(&dir, &[("lesson.md", LESSON)], Some(&publisher_key()));
+        // VCS metadata isn't course content.
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(dir.join(".git/HEAD"), "ref").unwrap();
+        assert!(verify_course_with(&dir, trusting(&publisher_key())).is_ok());
+    }
+
+    #[test]
+    fn rejects_tampered_files() {
+        let dir = scratch("tampered");
+        write_manifest(&dir, &[("lesson.md", "# Original")], None);
+        assert_eq!(failed_file(verify_course_with(&dir, no_keys)), "lesson.md");
+    }
+
+    #[test]
+    fn rejects_unlisted_files() {
+        let dir = scratch("unlisted");
+        write_manifest(&dir, &[("lesson.md", LESSON)], None);
+        std::fs::create_dir_all(dir.join("labs")).unwrap();
+        std::fs::write(dir.join("labs/extra.sh"), "rm -rf ~").unwrap();
+        assert_eq!(
+            failed_file(verify_course_with(&dir, no_keys)),
+            "labs/extra.sh"
+        );
+    }
+
+    #[test]
+    fn rejects_missing_files() {
+        let dir = scratch("missing");
+        write_manifest(&dir, &[("lesson.md", LESSON), ("gone.md", "x")], None);
+        assert_eq!(failed_file(verify_course_with(&dir, no_keys)), "gone.md");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks() {
+        let dir = scratch("symlink");
+        write_manifest(&dir, &[("lesson.md", LESSON)], None);
+        std::os::unix::fs::symlink(&dir, dir.join("loop")).unwrap();
+        assert_eq!(failed_file(verify_course_with(&dir, no_keys)), "loop");
+    }
+
+    #[test]
+    fn rejects_bad_signatures() {
+        let dir = scratch("forged");
+        let forger = SigningKey::from_bytes(&[9; 32]);
+        write_manifest(&dir, &[("lesson.md", LESSON)], Some(&forger));
+        assert_eq!(
+            failed_file(verify_course_with(&dir, trusting(&publisher_key()))),
+            "handhold.yaml"
+        );
+    }
+
+    #[test]
+    fn rejects_edits_after_signing() {
+        let dir = scratch("edited");
+        write_manifest(&dir, &[("lesson.md", LESSON)], Some(&publisher_key()));
+        let path = dir.join("handhold.yaml");
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, text.replace("title: T", "title: Free prize")).unwrap();
+        assert_eq!(
+            failed_file(verify_course_with(&dir, trusting(&publisher_key()))),
+            "handhold.yaml"
+        );
+    }
+
+    #[test]
+    fn rejects_untrusted_publishers() {
+        let dir = scratch("untrusted");
+        write_manifest(&dir, &[("lesson.md", LESSON)], Some(&publisher_key()));
+        let result = verify_course_with(&dir, no_keys);
+        assert!(matches!(result, Err(IntegrityError::UntrustedPublisher(p)) if p == "acme"));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::db::Db;
+use crate::paths::courses_dir;
+use rusqlite::params;
+use std::path::Path;
+use tauri::State;
+
+use super::import::{parse_manifest, register_course};
+use super::integrity::verify_course;
+use super::skipped_dir;
+use super::source::hash_id;
+use super::types::ImportResult;
+use super::validate::{has_errors, validate_course};
+
+/// Scheme for courses that point at an author's working tree.
+/// Anything under this scheme is never removed from disk by the app.
+pub(super) const LINK_SCHEME: &str = "link://";
+
+pub(super) fn is_linked(source_url: &str) -> bool {
+    source_url.starts_with(LINK_SCHEME)
+}
+
+/// Copies a course folder without the directories `skipped_dir` names.
+/// Symlinks are left behind: following one could loop forever or pull in
+/// files from outside the course.
+fn copy_course_dir(src: &Path, dst: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dst)
+        .map_err(|e| format!("Failed to create dir {}: {e}", dst.display()))?;
+    let entries =
+        std::fs::read_dir(src).map_err(|e| format!("Failed to read dir {}: {e}", src.display()))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read entry: {e}"))?;
+        let file_type = entry
+            .file_type()
+            .map_err(|e| format!("Failed to read entry: {e}"))?;
+        let name = entry.file_name();
+        let src_path = entry.path();
+        let dst_path = dst.join(&name);
+        if file_type.is_dir() {
+            if !skipped_dir(&name.to_string_lossy()) {
+                copy_course_dir(&src_path, &dst_path)?;
+            }
+        } else if file_type.is_file() {
+            std::fs::copy(&src_path, &dst_path).map_err(|e| {
+                format!(
+                    "Failed to copy {} → {}: {e}",
+                    src_path.display(),
+                    dst_path.display()
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Registers a course folder from anywhere on disk.
+///
+/// `link: false` copies it into the courses directory under its folder name,
+/// registered as `local://{dirname}` exactly as `course_sync` would find it.
+/// `link: true` leaves the files in place and registers `link://{path}`,
+/// so edits in the author's tree show up on the next step load.
+#[tauri::command]
+pub async fn course_import_local(
+    db: State<'_, Db>,
+    path: String,
+    link: bool,
+) -> Result<ImportResult, String> {
+    let Ok(src) = std::fs::canonicalize(&path) else {
+        return Ok(ImportResult::InvalidUrl);
+    };
+    if !src.is_dir() {
+        return Ok(ImportResult::InvalidUrl);
+    }
+
+    let Ok(manifest_text) = std::fs::read_to_string(src.join("handhold.yaml")) else {
+        return Ok(ImportResult::NoManifest);
+    };
+    let manifest = match parse_manifest(&manifest_text) {
+        Ok(m) => m,
+        Err(e) => return Ok(e.into()),
+    };
+    let diagnostics = validate_course(&src);
+    if has_errors(&diagnostics) {
+        return Ok(ImportResult::Invalid { diagnostics });
+    }
+
+    let Some(dirname) = src.file_name().map(|n| n.to_string_lossy().to_string()) else {
+        return Ok(ImportResult::InvalidUrl);
+    };
+
+    // A folder already inside the courses directory is registered in place.
+    let in_courses_dir = src.parent() == std::fs::canonicalize(courses_dir()).ok().as_deref();
+
+    let (source_url, dest) = if link {
+        (
+            format!("{LINK_SCHEME}{}", src.to_string_lossy()),
+            src.clone(),
+        )
+    } else if in_courses_dir {
+        (format!("local://{dirname}"), src.clone())
+    } else {
+        (format!("local://{dirname}"), courses_dir().join(&dirname))
+    };
+    let id = hash_id(&source_url);
+    let local_path = dest.to_string_lossy().to_string();
+
+    {
+        let conn = db.0.lock();
+        let exists: bool = conn
+            .query_row(
+                "SELECT count(*) > 0 FROM course WHERE id = ?1 OR source_url = ?2 OR local_path = ?3",
+                params![&id, &source_url, &local_path],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if exists {
+            return Ok(ImportResult::AlreadyExists);
+        }
+    }
+
+    if dest != src {
+        if dest.exists() {
+            return Ok(ImportResult::AlreadyExists);
+        }
+        std::fs::create_dir_all(&dest)
+            .map_err(|e| format!("Failed to create course directory: {e}"))?;
+        if let Err(e) = copy_course_dir(&src, &dest) {
+            let _ = std::fs::remove_dir_all(&dest);
+            return Ok(ImportResult::DownloadFailed { reason: e });
+        }
+    }
+
+    // Checked on the copy that gets registered, as for downloaded courses.
+    if let Err(e) = verify_course(&dest) {
+        if dest != src {
+            let _ = std::fs::remove_dir_all(&dest);
+        }
+        return Ok(e.into());
+    }
+
+    let mut conn = db.0.lock();
+    let course = register_course(&mut conn, &id, &source_url, &local_path, &manifest, None)?;
+    Ok(ImportResult::Ok { course })
+}
@@ -0,0 +1,91 @@
+use std::path::Path;
+
+use super::download::safe_relative;
+use super::gating::resolve_step;
+use super::schema::load_manifest;
+use super::types::Manifest;
+
+/// The locale to resolve when the caller doesn't pass one.
+pub(super) fn preferred_locale(requested: Option<String>) -> Option<String> {
+    requested.or_else(|| crate::settings::current().preferred_locale)
+}
+
+/// The declared locale that best matches `preferred`: the exact tag, then
+/// its language alone (`es-MX` → `es`), ignoring case.
+pub(super) fn pick_locale(manifest: &Manifest, preferred: &str) -> Option<String> {
+    let preferred = preferred.trim().replace('_', "-");
+    let language = preferred.split('-').next().unwrap_or_default();
+    let find = |wanted: &str| {
+        manifest
+            .locales
+            .keys()
+            .find(|tag| !wanted.is_empty() && tag.eq_ignore_ascii_case(wanted))
+            .cloned()
+    };
+    find(&preferred).or_else(|| find(language))
+}
+
+/// Rewrites `manifest` into its variant for `preferred`. Anything the locale
+/// leaves out keeps the base text, and a localized step path is only used
+/// when it exists under `dir`, so a partial translation still opens.
+pub(super) fn localize(manifest: &mut Manifest, dir: &Path, preferred: Option<&str>) {
+    let Some(tag) = preferred.and_then(|p| pick_locale(manifest, p)) else {
+        return;
+    };
+    let Some(locale) = manifest.locales.remove(&tag) else {
+        return;
+    };
+
+    if let Some(title) = &locale.title {
+        manifest.title.clone_from(title);
+    }
+    if let Some(description) = &locale.description {
+        manifest.description.clone_from(description);
+    }
+    for chapter in &mut manifest.chapters {
+        if let Some(title) = locale.chapters.get(&chapter.title) {
+            chapter.title.clone_from(title);
+        }
+    }
+
+    // Resolve every key against the base paths before any are rewritten.
+    let targets: Vec<(usize, &_)> = locale
+        .steps
+        .iter()
+        .filter_map(|(key, localized)| Some((resolve_step(manifest, key)?, localized)))
+        .collect();
+    for (index, localized) in targets {
+        let step = &mut manifest.steps[index];
+        if let Some(title) = &localized.title {
+            step.title.clone_from(title);
+        }
+        if let Some(objectives) = &localized.objectives {
+            step.objectives.clone_from(objectives);
+        }
+        if let Some(path) = &localized.path
+            && let Some(relative) = safe_relative(path)
+            && dir.join(relative).exists()
+        {
+            step.path.clone_from(path);
+        }
+    }
+
+    manifest.locales.insert(tag.clone(), locale);
+    manifest.locale = Some(tag);
+}
+
+/// The path to read for a step requested by its base path. Paths that are
+/// already localized, or that the manifest doesn't list, come back as-is.
+pub(super) fn localized_step_path(dir: &Path, step_path: &str, preferred: Option<&str>) -> String {
+    let Ok(text) = std::fs::read_to_string(dir.join("handhold.yaml")) else {
+        return step_path.to_string();
+    };
+    let Ok(mut manifest) = load_manifest(&text) else {
+        return step_path.to_string();
+    };
+    let Some(index) = manifest.steps.iter().position(|s| s.path == step_path) else {
+        return step_path.to_string();
+    };
+    localize(&mut manifest, dir, preferred);
+    manifest.steps.swap_remove(index).path
+}
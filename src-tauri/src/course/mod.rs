@@ -1,10 +1,24 @@
+mod backup;
+mod cache;
+mod catalog;
+mod credentials;
+mod deep_link;
 mod download;
+mod gating;
 mod import;
+mod integrity;
+mod local;
+mod locale;
+mod notes;
+mod package;
 mod progress;
 mod queries;
+mod schema;
 mod source;
 mod sync;
 pub mod types;
+mod update;
+mod validate;
 
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -15,9 +29,27 @@ fn now_ms() -> i64 {
         .as_millis() as i64
 }
 
+/// Directories that are never course content: VCS metadata and build
+/// output. Extraction, local copies, packages and integrity checks all
+/// leave them out, so each sees the same set of course files.
+fn skipped_dir(name: &str) -> bool {
+    matches!(name, ".git" | ".hg" | ".svn") || crate::paths::IGNORED_DIRS.contains(&name)
+}
+
 // Glob re-exports forward both the public command functions and
 // the hidden __cmd__ items that tauri::generate_handler! needs.
+pub use backup::*;
+pub use catalog::*;
+pub use credentials::*;
+pub use deep_link::*;
+pub use gating::*;
 pub use import::*;
+pub use integrity::*;
+pub use local::*;
+pub use notes::*;
+pub use package::*;
 pub use progress::*;
 pub use queries::*;
 pub use sync::*;
+pub use update::*;
+pub use validate::*;
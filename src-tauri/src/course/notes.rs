@@ -0,0 +1,352 @@
+use crate::db::Db;
+use rusqlite::{Connection, Row, params};
+use std::collections::HashMap;
+use tauri::State;
+
+use super::gating::installed_manifest;
+use super::now_ms;
+use super::types::{Note, NoteAnchor, NoteKind};
+
+/// Columns `read_note` expects, with the step's current index joined in.
+const SELECT_NOTE: &str = "SELECT n.id, n.course_id, n.step_id, cs.step_index, n.kind,
+        n.slide_id, n.file_path, n.line_start, n.line_end, n.quote, n.body,
+        n.created_at, n.updated_at
+    FROM note n
+    LEFT JOIN course_step cs ON cs.course_id = n.course_id AND cs.step_id = n.step_id";
+
+fn kind_name(kind: NoteKind) -> &'static str {
+    match kind {
+        NoteKind::Note => "note",
+        NoteKind::Bookmark => "bookmark",
+        NoteKind::Highlight => "highlight",
+    }
+}
+
+fn read_note(row: &Row) -> rusqlite::Result<Note> {
+    let kind = match row.get::<_, String>(4)?.as_str() {
+        "bookmark" => NoteKind::Bookmark,
+        "highlight" => NoteKind::Highlight,
+        _ => NoteKind::Note,
+    };
+    let slide_id: Option<String> = row.get(5)?;
+    let file_path: Option<String> = row.get(6)?;
+    let anchor = match (slide_id, file_path) {
+        (Some(slide_id), _) => NoteAnchor::Slide { slide_id },
+        (None, Some(path)) => NoteAnchor::LabFile {
+            path,
+            line_start: row.get(7)?,
+            line_end: row.get(8)?,
+        },
+        (None, None) => NoteAnchor::Step,
+    };
+    Ok(Note {
+        id: row.get(0)?,
+        course_id: row.get(1)?,
+        step_id: row.get(2)?,
+        step_index: row.get(3)?,
+        kind,
+        anchor,
+        quote: row.get(9)?,
+        body: row.get(10)?,
+        created_at: row.get(11)?,
+        updated_at: row.get(12)?,
+    })
+}
+
+fn get_note(conn: &Connection, id: i64) -> Result<Note, String> {
+    conn.query_row(
+        &format!("{SELECT_NOTE} WHERE n.id = ?1"),
+        params![id],
+        read_note,
+    )
+    .map_err(|e| format!("Note not found: {e}"))
+}
+
+fn query_notes(
+    conn: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<Note>, String> {
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| format!("Failed to query notes: {e}"))?;
+    stmt.query_map(params, read_note)
+        .map_err(|e| format!("Failed to read notes: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect notes: {e}"))
+}
+
+/// Rejects notes the UI couldn't show or jump back to.
+fn check(
+    kind: NoteKind,
+    anchor: &NoteAnchor,
+    body: &str,
+    quote: Option<&str>,
+) -> Result<(), String> {
+    match anchor {
+        NoteAnchor::Step => {}
+        NoteAnchor::Slide { slide_id } if slide_id.is_empty() => {
+            return Err("Slide id is empty".to_string());
+        }
+        NoteAnchor::Slide { .. } => {}
+        NoteAnchor::LabFile { path, .. } if path.is_empty() => {
+            return Err("Lab file path is empty".to_string());
+        }
+        NoteAnchor::LabFile {
+            line_start,
+            line_end,
+            ..
+        } => match (line_start, line_end) {
+            (None, Some(_)) => return Err("Line range has an end but no start".to_string()),
+            (Some(start), _) if *start < 1 => {
+                return Err("Line numbers start at 1".to_string());
+            }
+            (Some(start), Some(end)) if end < start => {
+                return Err(format!("Line range {start}-{end} ends before it starts"));
+            }
+            _ => {}
+        },
+    }
+    let has_lines = matches!(
+        anchor,
+        NoteAnchor::LabFile {
+            line_start: Some(_),
+            ..
+        }
+    );
+    match kind {
+        NoteKind::Note if body.trim().is_empty() => Err("A note needs some text".to_string()),
+        NoteKind::Highlight if quote.is_none_or(|q| q.trim().is_empty()) && !has_lines => {
+            Err("A highlight needs the highlighted text or a line range".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn note_create(
+    db: State<'_, Db>,
+    course_id: String,
+    step_index: i64,
+    kind: NoteKind,
+    anchor: NoteAnchor,
+    body: String,
+    quote: Option<String>,
+) -> Result<Note, String> {
+    check(kind, &anchor, &body, quote.as_deref())?;
+    let (slide_id, file_path, line_start, line_end) = match anchor {
+        NoteAnchor::Step => (None, None, None, None),
+        NoteAnchor::Slide { slide_id } => (Some(slide_id), None, None, None),
+        NoteAnchor::LabFile {
+            path,
+            line_start,
+            line_end,
+        } => (None, Some(path), line_start, line_end),
+    };
+    let conn = db.0.lock();
+    let inserted = conn
+        .execute(
+            "INSERT INTO note (course_id, step_id, kind, slide_id, file_path, line_start,
+                               line_end, quote, body, created_at, updated_at)
+             SELECT course_id, step_id, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10 FROM course_step
+             WHERE course_id = ?1 AND step_index = ?2",
+            params![
+                &course_id,
+                step_index,
+                kind_name(kind),
+                slide_id,
+                file_path,
+                line_start,
+                line_end,
+                quote,
+                body,
+                now_ms()
+            ],
+        )
+        .map_err(|e| format!("Failed to save note: {e}"))?;
+    if inserted == 0 {
+        return Err(format!("Step {step_index} not found in {course_id}"));
+    }
+    get_note(&conn, conn.last_insert_rowid())
+}
+
+/// Replaces a note's text. Its kind and anchor are fixed once created.
+#[tauri::command]
+pub async fn note_update(
+    db: State<'_, Db>,
+    id: i64,
+    body: String,
+    quote: Option<String>,
+) -> Result<Note, String> {
+    let conn = db.0.lock();
+    let note = get_note(&conn, id)?;
+    check(note.kind, &note.anchor, &body, quote.as_deref())?;
+    conn.execute(
+        "UPDATE note SET body = ?2, quote = ?3, updated_at = ?4 WHERE id = ?1",
+        params![id, body, quote, now_ms()],
+    )
+    .map_err(|e| format!("Failed to update note: {e}"))?;
+    get_note(&conn, id)
+}
+
+#[tauri::command]
+pub async fn note_delete(db: State<'_, Db>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock();
+    conn.execute("DELETE FROM note WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete note: {e}"))?;
+    Ok(())
+}
+
+/// A course's notes in step order, oldest first within a step. Notes on
+/// steps the course no longer has come last.
+#[tauri::command]
+pub async fn note_list(
+    db: State<'_, Db>,
+    course_id: String,
+    step_index: Option<i64>,
+) -> Result<Vec<Note>, String> {
+    let conn = db.0.lock();
+    query_notes(
+        &conn,
+        &format!(
+            "{SELECT_NOTE}
+             WHERE n.course_id = ?1 AND (?2 IS NULL OR cs.step_index = ?2)
+             ORDER BY cs.step_index IS NULL, cs.step_index, n.step_id, n.created_at, n.id"
+        ),
+        params![&course_id, step_index],
+    )
+}
+
+/// Full-text search over note text and highlighted quotes, best match first.
+/// `query` uses FTS5 syntax, as in `course_search`.
+#[tauri::command]
+pub async fn note_search(
+    db: State<'_, Db>,
+    query: String,
+    course_id: Option<String>,
+) -> Result<Vec<Note>, String> {
+    let conn = db.0.lock();
+    query_notes(
+        &conn,
+        &format!(
+            "{SELECT_NOTE}
+             JOIN note_search ns ON ns.rowid = n.id
+             WHERE note_search MATCH ?1 AND (?2 IS NULL OR n.course_id = ?2)
+             ORDER BY ns.rank"
+        ),
+        params![&query, course_id],
+    )
+}
+
+/// A backtick fence longer than any run inside `text`.
+fn code_fence(text: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn note_heading(note: &Note) -> String {
+    let kind = match note.kind {
+        NoteKind::Note => "Note",
+        NoteKind::Bookmark => "Bookmark",
+        NoteKind::Highlight => "Highlight",
+    };
+    match &note.anchor {
+        NoteAnchor::Step => kind.to_string(),
+        NoteAnchor::Slide { slide_id } => format!("{kind} · slide `{slide_id}`"),
+        NoteAnchor::LabFile {
+            path,
+            line_start,
+            line_end,
+        } => match (line_start, line_end) {
+            (Some(start), Some(end)) if end > start => {
+                format!("{kind} · `{path}` lines {start}–{end}")
+            }
+            (Some(start), _) => format!("{kind} · `{path}` line {start}"),
+            _ => format!("{kind} · `{path}`"),
+        },
+    }
+}
+
+/// Renders notes grouped under their steps. Quotes from lab files are code,
+/// so they go in a fenced block; quotes from slides are blockquotes.
+fn notes_markdown(title: &str, notes: &[Note], step_titles: &HashMap<String, String>) -> String {
+    let mut out = format!("# {title} — notes\n");
+    let mut current_step: Option<&str> = None;
+    for note in notes {
+        if current_step != Some(note.step_id.as_str()) {
+            current_step = Some(&note.step_id);
+            let step_title = step_titles
+                .get(&note.step_id)
+                .map_or(note.step_id.as_str(), String::as_str);
+            match note.step_index {
+                Some(index) => out.push_str(&format!("\n## {}. {step_title}\n", index + 1)),
+                None => out.push_str(&format!("\n## {step_title} (removed from the course)\n")),
+            }
+        }
+        out.push_str(&format!("\n### {}\n", note_heading(note)));
+        if let Some(quote) = note.quote.as_deref().filter(|q| !q.trim().is_empty()) {
+            out.push('\n');
+            if matches!(note.anchor, NoteAnchor::LabFile { .. }) {
+                let fence = code_fence(quote);
+                out.push_str(&format!("{fence}\n{}\n{fence}\n", quote.trim_end()));
+            } else {
+                for line in quote.trim_end().lines() {
+                    match line {
+                        "" => out.push_str(">\n"),
+                        line => out.push_str(&format!("> {line}\n")),
+                    }
+                }
+            }
+        }
+        if !note.body.trim().is_empty() {
+            out.push_str(&format!("\n{}\n", note.body.trim_end()));
+        }
+    }
+    out
+}
+
+/// Writes a course's notes to `dest` as Markdown and returns how many.
+#[tauri::command]
+pub async fn note_export(
+    db: State<'_, Db>,
+    course_id: String,
+    dest: String,
+) -> Result<usize, String> {
+    let conn = db.0.lock();
+    let title: String = conn
+        .query_row(
+            "SELECT title FROM course WHERE id = ?1",
+            params![&course_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Course not found: {e}"))?;
+    let notes = query_notes(
+        &conn,
+        &format!(
+            "{SELECT_NOTE}
+             WHERE n.course_id = ?1
+             ORDER BY cs.step_index IS NULL, cs.step_index, n.step_id, n.created_at, n.id"
+        ),
+        params![&course_id],
+    )?;
+    drop(conn);
+
+    // Titles come from the manifest; a course whose manifest can't be read
+    // still exports, headed by step ids.
+    let step_titles: HashMap<String, String> = installed_manifest(&db, &course_id)
+        .map(|manifest| {
+            manifest
+                .steps
+                .into_iter()
+                .map(|step| (step.id, step.title))
+                .collect()
+        })
+        .unwrap_or_default();
+    let markdown = notes_markdown(&title, &notes, &step_titles);
+    std::fs::write(&dest, markdown).map_err(|e| format!("Failed to write {dest}: {e}"))?;
+    Ok(notes.len())
+}
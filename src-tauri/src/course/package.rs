@@ -0,0 +1,260 @@
+use crate::db::Db;
+use crate::paths::courses_dir;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+use tauri::State;
+
+use super::download::{Transfer, extract_tarball};
+use super::import::{parse_manifest, register_course};
+use super::integrity::verify_course;
+use super::local::is_linked;
+use super::now_ms;
+use super::skipped_dir;
+use super::source::{hash_id, parse_source_url, source_id};
+use super::types::ImportResult;
+use super::validate::{has_errors, validate_course};
+
+/// Offline course package layout (gzipped tar):
+///
+/// ```text
+/// handhold-package.json   PackageHeader
+/// course/handhold.yaml
+/// course/...              steps, labs, audio/ TTS bundle
+/// ```
+const HEADER_NAME: &str = "handhold-package.json";
+const COURSE_DIR: &str = "course";
+const PACKAGE_FORMAT: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageHeader {
+    format: u32,
+    source_url: String,
+    /// Commit the exported content was installed from, when known.
+    #[serde(default)]
+    revision: Option<String>,
+    title: String,
+    exported_at: i64,
+    app_version: String,
+}
+
+/// Appends `dir` under `prefix`, skipping VCS metadata and build output.
+/// Linked courses are an author's working tree and usually carry both.
+fn append_course_dir<W: std::io::Write>(
+    builder: &mut tar::Builder<W>,
+    dir: &Path,
+    prefix: &Path,
+) -> Result<(), String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("Failed to read dir {}: {e}", dir.display()))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read entry: {e}"))?;
+        let name = entry.file_name();
+        let name_str = name.to_string_lossy();
+        let path = entry.path();
+        let archive_path = prefix.join(&name);
+
+        if path.is_dir() {
+            if skipped_dir(&name_str) {
+                continue;
+            }
+            append_course_dir(builder, &path, &archive_path)?;
+        } else {
+            builder
+                .append_path_with_name(&path, &archive_path)
+                .map_err(|e| format!("Failed to add {}: {e}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes an installed course to a self-contained `.handhold` package.
+#[tauri::command]
+pub async fn course_export(db: State<'_, Db>, id: String, dest: String) -> Result<(), String> {
+    let (source_url, local_path, title, revision) = {
+        let conn = db.0.lock();
+        conn.query_row(
+            "SELECT source_url, local_path, title, revision FROM course WHERE id = ?1",
+            params![&id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            },
+        )
+        .map_err(|e| format!("Course not found: {e}"))?
+    };
+
+    // A package is always a copy — it must not import as a link to a path
+    // that only exists on the exporting machine.
+    let source_url = if is_linked(&source_url) {
+        let dirname = Path::new(&local_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| id.clone());
+        format!("local://{dirname}")
+    } else {
+        source_url
+    };
+
+    let header = PackageHeader {
+        format: PACKAGE_FORMAT,
+        source_url,
+        revision,
+        title,
+        exported_at: now_ms(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    let header_json = serde_json::to_vec_pretty(&header)
+        .map_err(|e| format!("Failed to serialize package header: {e}"))?;
+
+    let file = std::fs::File::create(&dest).map_err(|e| format!("Failed to create {dest}: {e}"))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let mut tar_header = tar::Header::new_gnu();
+    tar_header.set_size(header_json.len() as u64);
+    tar_header.set_mode(0o644);
+    tar_header.set_mtime((header.exported_at / 1000) as u64);
+    tar_header.set_cksum();
+    builder
+        .append_data(&mut tar_header, HEADER_NAME, header_json.as_slice())
+        .map_err(|e| format!("Failed to write package header: {e}"))?;
+
+    append_course_dir(&mut builder, Path::new(&local_path), Path::new(COURSE_DIR))?;
+
+    builder
+        .into_inner()
+        .and_then(|gz| gz.finish())
+        .map_err(|e| format!("Failed to finish package: {e}"))?;
+    Ok(())
+}
+
+fn read_header(path: &Path) -> Result<PackageHeader, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open package: {e}"))?;
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    for entry in archive
+        .entries()
+        .map_err(|e| format!("Failed to read package: {e}"))?
+    {
+        let mut entry = entry.map_err(|e| format!("Package entry error: {e}"))?;
+        let is_header = entry
+            .path()
+            .is_ok_and(|p| p.as_os_str() == std::ffi::OsStr::new(HEADER_NAME));
+        if !is_header {
+            continue;
+        }
+        let mut raw = String::new();
+        entry
+            .read_to_string(&mut raw)
+            .map_err(|e| format!("Failed to read package header: {e}"))?;
+        return serde_json::from_str(&raw).map_err(|e| format!("Invalid package header: {e}"));
+    }
+    Err(format!("Missing {HEADER_NAME}"))
+}
+
+/// Installs a `.handhold` package without touching the network.
+#[tauri::command]
+pub async fn course_import_archive(
+    db: State<'_, Db>,
+    path: String,
+) -> Result<ImportResult, String> {
+    let archive_path = Path::new(&path);
+    let header = match read_header(archive_path) {
+        Ok(h) => h,
+        Err(reason) => return Ok(ImportResult::BadPackage { reason }),
+    };
+    if header.format > PACKAGE_FORMAT {
+        return Ok(ImportResult::BadPackage {
+            reason: format!(
+                "Package format {} is newer than this app supports ({PACKAGE_FORMAT})",
+                header.format
+            ),
+        });
+    }
+    if header.source_url.is_empty() || is_linked(&header.source_url) {
+        return Ok(ImportResult::BadPackage {
+            reason: "Package has no usable source URL".to_string(),
+        });
+    }
+
+    // Forge sources get the same id `course_import` would assign, so a
+    // later online import of the same course is recognised as a duplicate.
+    let forges = crate::settings::current().forges;
+    let id = parse_source_url(&header.source_url, &forges)
+        .map_or_else(|| hash_id(&header.source_url), |s| source_id(&s));
+
+    {
+        let conn = db.0.lock();
+        let exists: bool = conn
+            .query_row(
+                "SELECT count(*) > 0 FROM course WHERE id = ?1 OR source_url = ?2",
+                params![&id, &header.source_url],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if exists {
+            return Ok(ImportResult::AlreadyExists);
+        }
+    }
+
+    let dest = courses_dir().join(&id);
+    if dest.exists() {
+        std::fs::remove_dir_all(&dest)
+            .map_err(|e| format!("Failed to clean existing directory: {e}"))?;
+    }
+    std::fs::create_dir_all(&dest)
+        .map_err(|e| format!("Failed to create course directory: {e}"))?;
+
+    // Everything under `course/` — the header sits at the root and is skipped.
+    let extracted = std::fs::File::open(archive_path)
+        .map_err(|e| format!("Failed to open package: {e}"))
+        .and_then(|file| extract_tarball(file, "", &dest, &Transfer::silent()));
+    if let Err(reason) = extracted {
+        let _ = std::fs::remove_dir_all(&dest);
+        return Ok(ImportResult::BadPackage { reason });
+    }
+
+    let manifest = match std::fs::read_to_string(dest.join("handhold.yaml")) {
+        Ok(text) => parse_manifest(&text),
+        Err(_) => {
+            let _ = std::fs::remove_dir_all(&dest);
+            return Ok(ImportResult::NoManifest);
+        }
+    };
+    let manifest = match manifest {
+        Ok(m) => m,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&dest);
+            return Ok(e.into());
+        }
+    };
+
+    if let Err(e) = verify_course(&dest) {
+        let _ = std::fs::remove_dir_all(&dest);
+        return Ok(e.into());
+    }
+    let diagnostics = validate_course(&dest);
+    if has_errors(&diagnostics) {
+        let _ = std::fs::remove_dir_all(&dest);
+        return Ok(ImportResult::Invalid { diagnostics });
+    }
+
+    let local_path = dest.to_string_lossy().to_string();
+    let mut conn = db.0.lock();
+    let course = register_course(
+        &mut conn,
+        &id,
+        &header.source_url,
+        &local_path,
+        &manifest,
+        header.revision.as_deref(),
+    )?;
+    Ok(ImportResult::Ok { course })
+}
@@ -1,21 +1,174 @@
 use crate::db::Db;
-use rusqlite::{OptionalExtension, params};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::path::Path;
 use tauri::State;
 
+use super::gating::{check_unlocked, installed_manifest};
+use super::import::store_steps;
 use super::now_ms;
-use super::types::{Route, SlidePosition};
+use super::schema::load_manifest;
+use super::types::{
+    CourseTime, Manifest, ProgressStats, Route, SlidePosition, StepKind, StepTime, Streak,
+    WeekCompletions,
+};
 
+/// Most active time one heartbeat can add. The frontend beats every 30s, so
+/// anything longer is a suspended laptop or a stalled timer, not study.
+const MAX_HEARTBEAT_MS: i64 = 2 * 60 * 1000;
+
+/// Copies a course's index-keyed rows from each `legacy_*` table into its
+/// step_id-keyed successor, joining through `course_step`.
+const LEGACY_COPIES: &[(&str, &str)] = &[
+    (
+        "legacy_step_completion",
+        "INSERT OR IGNORE INTO step_completion (course_id, step_id, completed_at)
+         SELECT l.course_id, cs.step_id, l.completed_at FROM legacy_step_completion l
+         JOIN course_step cs ON cs.course_id = l.course_id AND cs.step_index = l.step_index
+         WHERE l.course_id = ?1",
+    ),
+    (
+        "legacy_step_position",
+        "INSERT OR IGNORE INTO step_position (course_id, step_id, slide_index, slide_count)
+         SELECT l.course_id, cs.step_id, l.slide_index, l.slide_count FROM legacy_step_position l
+         JOIN course_step cs ON cs.course_id = l.course_id AND cs.step_index = l.step_index
+         WHERE l.course_id = ?1",
+    ),
+    (
+        "legacy_slide_completion",
+        "INSERT OR IGNORE INTO slide_completion (course_id, step_id, slide_id)
+         SELECT l.course_id, cs.step_id, l.slide_id FROM legacy_slide_completion l
+         JOIN course_step cs ON cs.course_id = l.course_id AND cs.step_index = l.step_index
+         WHERE l.course_id = ?1",
+    ),
+];
+
+fn table_exists(conn: &Connection, name: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT count(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        params![name],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("Failed to check for {name}: {e}"))
+}
+
+/// Gives a course that predates step ids its index → id map, built from
+/// `manifest` — the one its index-keyed progress was recorded against —
+/// and moves that progress over. No-op once the course has a map.
+pub(super) fn adopt_legacy_progress(
+    conn: &Connection,
+    id: &str,
+    manifest: &Manifest,
+) -> Result<(), String> {
+    let mapped: bool = conn
+        .query_row(
+            "SELECT count(*) > 0 FROM course_step WHERE course_id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to read steps: {e}"))?;
+    if mapped {
+        return Ok(());
+    }
+    store_steps(conn, id, manifest)?;
+    for (legacy, copy) in LEGACY_COPIES {
+        if !table_exists(conn, legacy)? {
+            continue;
+        }
+        conn.execute(copy, params![id])
+            .map_err(|e| format!("Failed to migrate {legacy}: {e}"))?;
+        conn.execute(
+            &format!("DELETE FROM {legacy} WHERE course_id = ?1"),
+            params![id],
+        )
+        .map_err(|e| format!("Failed to clear {legacy}: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Startup pass that maps every installed course onto step ids, then drops
+/// the `legacy_*` tables once nothing is left in them. Courses whose
+/// manifest can't be read keep their rows until a later run or update.
+pub fn migrate_step_ids(db: &Db) -> Result<(), String> {
+    let mut conn = db.0.lock();
+    let pending: Vec<(String, String)> = conn
+        .prepare(
+            "SELECT id, local_path FROM course c
+             WHERE NOT EXISTS (SELECT 1 FROM course_step cs WHERE cs.course_id = c.id)",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect()
+        })
+        .map_err(|e| format!("Failed to list courses: {e}"))?;
+
+    for (id, local_path) in pending {
+        let manifest = match std::fs::read_to_string(Path::new(&local_path).join("handhold.yaml")) {
+            Ok(text) => match load_manifest(&text) {
+                Ok(m) => m,
+                Err(e) => {
+                    eprintln!("[migrate] {id}: failed to parse manifest: {e}");
+                    continue;
+                }
+            },
+            Err(e) => {
+                eprintln!("[migrate] {id}: failed to read manifest: {e}");
+                continue;
+            }
+        };
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to begin migration: {e}"))?;
+        adopt_legacy_progress(&tx, &id, &manifest)?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit migration: {e}"))?;
+    }
+
+    for (legacy, _) in LEGACY_COPIES {
+        if !table_exists(&conn, legacy)? {
+            continue;
+        }
+        let remaining: i64 = conn
+            .query_row(&format!("SELECT count(*) FROM {legacy}"), [], |row| {
+                row.get(0)
+            })
+            .map_err(|e| format!("Failed to count {legacy}: {e}"))?;
+        if remaining == 0 {
+            conn.execute_batch(&format!("DROP TABLE {legacy};"))
+                .map_err(|e| format!("Failed to drop {legacy}: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// The stable id of the step at `step_index`. Progress is stored by id, so a
+/// step without a mapping has nowhere to go and is an error, not a no-op.
+fn step_id_at(conn: &Connection, course_id: &str, step_index: i64) -> Result<String, String> {
+    conn.query_row(
+        "SELECT step_id FROM course_step WHERE course_id = ?1 AND step_index = ?2",
+        params![course_id, step_index],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("Failed to read steps: {e}"))?
+    .ok_or_else(|| format!("Step {step_index} not found in {course_id}"))
+}
+
+/// Refuses steps whose `requires:` aren't met unless the course enables
+/// `freeNavigation`.
 #[tauri::command]
 pub async fn step_complete(
     db: State<'_, Db>,
     course_id: String,
     step_index: i64,
 ) -> Result<(), String> {
+    let manifest = installed_manifest(&db, &course_id)?;
     let conn = db.0.lock();
+    check_unlocked(&conn, &course_id, &manifest, step_index)?;
+    let step_id = step_id_at(&conn, &course_id, step_index)?;
     conn.execute(
-        "INSERT OR IGNORE INTO step_completion (course_id, step_index, completed_at)
+        "INSERT OR IGNORE INTO step_completion (course_id, step_id, completed_at)
          VALUES (?1, ?2, ?3)",
-        params![&course_id, step_index, now_ms()],
+        params![&course_id, &step_id, now_ms()],
     )
     .map_err(|e| format!("Failed to mark step complete: {e}"))?;
     Ok(())
@@ -26,8 +179,9 @@ pub async fn step_progress(db: State<'_, Db>, course_id: String) -> Result<Vec<i
     let conn = db.0.lock();
     let mut stmt = conn
         .prepare(
-            "SELECT step_index FROM step_completion
-             WHERE course_id = ?1 ORDER BY step_index",
+            "SELECT cs.step_index FROM step_completion sc
+             JOIN course_step cs ON cs.course_id = sc.course_id AND cs.step_id = sc.step_id
+             WHERE sc.course_id = ?1 ORDER BY cs.step_index",
         )
         .map_err(|e| e.to_string())?;
 
@@ -48,13 +202,14 @@ pub async fn slide_position_save(
     slide_count: Option<i64>,
 ) -> Result<(), String> {
     let conn = db.0.lock();
+    let step_id = step_id_at(&conn, &course_id, step_index)?;
     conn.execute(
-        "INSERT INTO step_position (course_id, step_index, slide_index, slide_count)
+        "INSERT INTO step_position (course_id, step_id, slide_index, slide_count)
          VALUES (?1, ?2, ?3, ?4)
-         ON CONFLICT (course_id, step_index) DO UPDATE SET
+         ON CONFLICT (course_id, step_id) DO UPDATE SET
            slide_index = excluded.slide_index,
            slide_count = COALESCE(excluded.slide_count, step_position.slide_count)",
-        params![&course_id, step_index, slide_index, slide_count],
+        params![&course_id, &step_id, slide_index, slide_count],
     )
     .map_err(|e| format!("Failed to save slide position: {e}"))?;
     Ok(())
@@ -68,7 +223,9 @@ pub async fn slide_position_load(
 ) -> Result<Option<SlidePosition>, String> {
     let conn = db.0.lock();
     conn.query_row(
-        "SELECT slide_index, slide_count FROM step_position WHERE course_id = ?1 AND step_index = ?2",
+        "SELECT sp.slide_index, sp.slide_count FROM step_position sp
+         JOIN course_step cs ON cs.course_id = sp.course_id AND cs.step_id = sp.step_id
+         WHERE sp.course_id = ?1 AND cs.step_index = ?2",
         params![&course_id, step_index],
         |row| {
             Ok(SlidePosition {
@@ -89,10 +246,11 @@ pub async fn slide_complete(
     slide_id: String,
 ) -> Result<(), String> {
     let conn = db.0.lock();
+    let step_id = step_id_at(&conn, &course_id, step_index)?;
     conn.execute(
-        "INSERT OR IGNORE INTO slide_completion (course_id, step_index, slide_id)
+        "INSERT OR IGNORE INTO slide_completion (course_id, step_id, slide_id)
          VALUES (?1, ?2, ?3)",
-        params![&course_id, step_index, &slide_id],
+        params![&course_id, &step_id, &slide_id],
     )
     .map_err(|e| format!("Failed to save slide completion: {e}"))?;
     Ok(())
@@ -106,7 +264,11 @@ pub async fn slide_completions(
 ) -> Result<Vec<String>, String> {
     let conn = db.0.lock();
     let mut stmt = conn
-        .prepare("SELECT slide_id FROM slide_completion WHERE course_id = ?1 AND step_index = ?2")
+        .prepare(
+            "SELECT sc.slide_id FROM slide_completion sc
+             JOIN course_step cs ON cs.course_id = sc.course_id AND cs.step_id = sc.step_id
+             WHERE sc.course_id = ?1 AND cs.step_index = ?2",
+        )
         .map_err(|e| format!("Failed to query slide completions: {e}"))?;
     let ids = stmt
         .query_map(params![&course_id, step_index], |row| row.get(0))
@@ -116,10 +278,231 @@ pub async fn slide_completions(
     Ok(ids)
 }
 
+fn kind_name(kind: &StepKind) -> &'static str {
+    match kind {
+        StepKind::Lesson => "lesson",
+        StepKind::Lab => "lab",
+    }
+}
+
+/// Starts timing a step. `None` when the index doesn't name a step, e.g.
+/// while the course view is clamping an out-of-range one.
 #[tauri::command]
-pub async fn route_save(db: State<'_, Db>, route: Route) -> Result<(), String> {
+pub async fn study_session_open(
+    db: State<'_, Db>,
+    course_id: String,
+    step_index: i64,
+) -> Result<Option<i64>, String> {
+    let manifest = installed_manifest(&db, &course_id)?;
+    let Some(step) = usize::try_from(step_index)
+        .ok()
+        .and_then(|index| manifest.steps.get(index))
+    else {
+        return Ok(None);
+    };
+    let conn = db.0.lock();
+    let inserted = conn
+        .execute(
+            "INSERT INTO study_session (course_id, step_id, step_kind, opened_at)
+             SELECT course_id, step_id, ?3, ?4 FROM course_step
+             WHERE course_id = ?1 AND step_index = ?2",
+            params![&course_id, step_index, kind_name(&step.kind), now_ms()],
+        )
+        .map_err(|e| format!("Failed to open study session: {e}"))?;
+    Ok((inserted > 0).then(|| conn.last_insert_rowid()))
+}
+
+/// Adds `active_ms` to an open session, capped by the time since its last
+/// heartbeat so a runaway timer can't inflate the totals.
+fn record_activity(conn: &Connection, session_id: i64, active_ms: i64) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO study_heartbeat (session_id, at, active_ms)
+         SELECT s.id, ?2, max(0, min(?3, ?4, ?2 - COALESCE(
+             (SELECT max(h.at) FROM study_heartbeat h WHERE h.session_id = s.id),
+             s.opened_at
+         )))
+         FROM study_session s WHERE s.id = ?1 AND s.closed_at IS NULL",
+        params![session_id, now_ms(), active_ms, MAX_HEARTBEAT_MS],
+    )
+    .map_err(|e| format!("Failed to record study time: {e}"))?;
+    Ok(())
+}
+
+/// Records time the learner was active on the step since the last heartbeat.
+#[tauri::command]
+pub async fn study_heartbeat(
+    db: State<'_, Db>,
+    session_id: i64,
+    active_ms: i64,
+) -> Result<(), String> {
+    let conn = db.0.lock();
+    record_activity(&conn, session_id, active_ms)
+}
+
+/// Records the last stretch of activity and ends the session. Sessions a
+/// crash left open keep the time their heartbeats already recorded.
+#[tauri::command]
+pub async fn study_session_close(
+    db: State<'_, Db>,
+    session_id: i64,
+    active_ms: i64,
+) -> Result<(), String> {
     let conn = db.0.lock();
-    match &route {
+    record_activity(&conn, session_id, active_ms)?;
+    conn.execute(
+        "UPDATE study_session SET closed_at = ?2 WHERE id = ?1 AND closed_at IS NULL",
+        params![session_id, now_ms()],
+    )
+    .map_err(|e| format!("Failed to close study session: {e}"))?;
+    Ok(())
+}
+
+fn course_times(conn: &Connection) -> Result<Vec<CourseTime>, String> {
+    let mut courses: Vec<CourseTime> = conn
+        .prepare(
+            "SELECT s.course_id, c.title, sum(h.active_ms) FROM study_heartbeat h
+             JOIN study_session s ON s.id = h.session_id
+             JOIN course c ON c.id = s.course_id
+             GROUP BY s.course_id HAVING sum(h.active_ms) > 0
+             ORDER BY sum(h.active_ms) DESC",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                Ok(CourseTime {
+                    course_id: row.get(0)?,
+                    title: row.get(1)?,
+                    active_ms: row.get(2)?,
+                    steps: Vec::new(),
+                })
+            })?
+            .collect()
+        })
+        .map_err(|e| format!("Failed to total course time: {e}"))?;
+
+    let steps: Vec<(String, StepTime)> = conn
+        .prepare(
+            "SELECT s.course_id, s.step_id, cs.step_index, max(s.step_kind),
+                    sum(h.active_ms), count(DISTINCT s.id)
+             FROM study_heartbeat h
+             JOIN study_session s ON s.id = h.session_id
+             LEFT JOIN course_step cs ON cs.course_id = s.course_id AND cs.step_id = s.step_id
+             GROUP BY s.course_id, s.step_id HAVING sum(h.active_ms) > 0
+             ORDER BY s.course_id, cs.step_index IS NULL, cs.step_index, s.step_id",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                let kind: String = row.get(3)?;
+                Ok((
+                    row.get(0)?,
+                    StepTime {
+                        step_id: row.get(1)?,
+                        step_index: row.get(2)?,
+                        kind: if kind == "lab" {
+                            StepKind::Lab
+                        } else {
+                            StepKind::Lesson
+                        },
+                        active_ms: row.get(4)?,
+                        sessions: row.get(5)?,
+                    },
+                ))
+            })?
+            .collect()
+        })
+        .map_err(|e| format!("Failed to total step time: {e}"))?;
+
+    for (course_id, step) in steps {
+        if let Some(course) = courses.iter_mut().find(|c| c.course_id == course_id) {
+            course.steps.push(step);
+        }
+    }
+    Ok(courses)
+}
+
+/// Days count in local time. Runs are found by subtracting each day's rank
+/// from its date: consecutive days share the difference.
+fn streak(conn: &Connection) -> Result<Streak, String> {
+    conn.query_row(
+        "WITH days AS (
+             SELECT date(at / 1000, 'unixepoch', 'localtime') AS day
+             FROM study_heartbeat WHERE active_ms > 0
+             UNION
+             SELECT date(completed_at / 1000, 'unixepoch', 'localtime') FROM step_completion
+         ),
+         runs AS (
+             SELECT max(day) AS last_day, count(*) AS length FROM (
+                 SELECT day, julianday(day) - row_number() OVER (ORDER BY day) AS run FROM days
+             ) GROUP BY run
+         )
+         SELECT
+             COALESCE((SELECT length FROM runs
+                       WHERE last_day >= date('now', 'localtime', '-1 day')), 0),
+             COALESCE(max(length), 0),
+             max(last_day)
+         FROM runs",
+        [],
+        |row| {
+            Ok(Streak {
+                current_days: row.get(0)?,
+                longest_days: row.get(1)?,
+                last_active_day: row.get(2)?,
+            })
+        },
+    )
+    .map_err(|e| format!("Failed to compute streak: {e}"))
+}
+
+fn weekly_completions(conn: &Connection) -> Result<Vec<WeekCompletions>, String> {
+    // 'weekday 0' moves forward to Sunday, so six days back is that week's Monday.
+    conn.prepare(
+        "SELECT date(completed_at / 1000, 'unixepoch', 'localtime', 'weekday 0', '-6 days') AS week,
+                count(*)
+         FROM step_completion GROUP BY week ORDER BY week",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map([], |row| {
+            Ok(WeekCompletions {
+                week_start: row.get(0)?,
+                completions: row.get(1)?,
+            })
+        })?
+        .collect()
+    })
+    .map_err(|e| format!("Failed to count weekly completions: {e}"))
+}
+
+/// Mean total time over completed labs, and how many that covers.
+fn lab_average(conn: &Connection) -> Result<(Option<i64>, u32), String> {
+    conn.query_row(
+        "SELECT CAST(round(avg(total)) AS INTEGER), count(*) FROM (
+             SELECT sum(h.active_ms) AS total FROM study_heartbeat h
+             JOIN study_session s ON s.id = h.session_id
+             JOIN step_completion sc ON sc.course_id = s.course_id AND sc.step_id = s.step_id
+             WHERE s.step_kind = 'lab'
+             GROUP BY s.course_id, s.step_id
+         )",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .map_err(|e| format!("Failed to average lab time: {e}"))
+}
+
+#[tauri::command]
+pub async fn progress_stats(db: State<'_, Db>) -> Result<ProgressStats, String> {
+    let conn = db.0.lock();
+    let (average_lab_ms, completed_labs) = lab_average(&conn)?;
+    Ok(ProgressStats {
+        courses: course_times(&conn)?,
+        streak: streak(&conn)?,
+        weekly_completions: weekly_completions(&conn)?,
+        average_lab_ms,
+        completed_labs,
+    })
+}
+
+/// Persists where the app should open next.
+pub(super) fn save_route(conn: &Connection, route: &Route) -> Result<(), String> {
+    match route {
         Route::Browser => {
             conn.execute(
                 "UPDATE app_route SET kind = 'browser', course_id = NULL, step_index = NULL WHERE id = 1",
@@ -148,6 +531,12 @@ pub async fn route_save(db: State<'_, Db>, route: Route) -> Result<(), String> {
     Ok(())
 }
 
+#[tauri::command]
+pub async fn route_save(db: State<'_, Db>, route: Route) -> Result<(), String> {
+    let conn = db.0.lock();
+    save_route(&conn, &route)
+}
+
 #[tauri::command]
 pub async fn route_load(db: State<'_, Db>) -> Result<Route, String> {
     let conn = db.0.lock();
@@ -1,9 +1,14 @@
 use crate::db::Db;
 use rusqlite::params;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::State;
 
-use super::types::{CourseManifest, CourseRecord, LabData, Manifest, RawLabConfig};
+use super::local::is_linked;
+use super::locale::{localize, localized_step_path, preferred_locale};
+use super::schema::load_manifest;
+use super::source::hash_id;
+use super::sync::refresh_course;
+use super::types::{CourseManifest, CourseRecord, LabData, RawLabConfig};
 use crate::paths::workspaces_dir;
 
 pub(super) fn read_course_row(
@@ -12,7 +17,9 @@ pub(super) fn read_course_row(
 ) -> Result<CourseRecord, String> {
     let completed_steps: i64 = conn
         .query_row(
-            "SELECT count(*) FROM step_completion WHERE course_id = ?1",
+            "SELECT count(DISTINCT sc.step_id) FROM step_completion sc
+             JOIN course_step cs ON cs.course_id = sc.course_id AND cs.step_id = sc.step_id
+             WHERE sc.course_id = ?1 AND NOT cs.optional",
             params![id],
             |row| row.get(0),
         )
@@ -28,7 +35,8 @@ pub(super) fn read_course_row(
         .map_err(|e| e.to_string())?;
 
     conn.query_row(
-        "SELECT id, source_url, local_path, title, description, step_count, added_at
+        "SELECT id, source_url, local_path, title, description, step_count, added_at, revision,
+                step_count - (SELECT count(*) FROM course_step WHERE course_id = course.id AND optional)
          FROM course WHERE id = ?1",
         params![id],
         |row| {
@@ -39,7 +47,9 @@ pub(super) fn read_course_row(
                 title: row.get(3)?,
                 description: row.get(4)?,
                 step_count: row.get(5)?,
+                required_steps: row.get(8)?,
                 added_at: row.get(6)?,
+                revision: row.get(7)?,
                 completed_steps,
                 tags: tags.clone(),
             })
@@ -54,9 +64,12 @@ pub async fn course_list(db: State<'_, Db>) -> Result<Vec<CourseRecord>, String>
     let mut stmt = conn
         .prepare(
             "SELECT c.id, c.source_url, c.local_path, c.title, c.description,
-                    c.step_count, c.added_at, count(sc.step_index) as completed
+                    c.step_count, c.added_at, c.revision, count(DISTINCT cs.step_id) as completed,
+                    c.step_count - (SELECT count(*) FROM course_step WHERE course_id = c.id AND optional)
              FROM course c
              LEFT JOIN step_completion sc ON sc.course_id = c.id
+             LEFT JOIN course_step cs ON cs.course_id = sc.course_id AND cs.step_id = sc.step_id
+               AND NOT cs.optional
              GROUP BY c.id
              ORDER BY c.added_at DESC",
         )
@@ -71,8 +84,10 @@ pub async fn course_list(db: State<'_, Db>) -> Result<Vec<CourseRecord>, String>
                 title: row.get(3)?,
                 description: row.get(4)?,
                 step_count: row.get(5)?,
+                required_steps: row.get(9)?,
                 added_at: row.get(6)?,
-                completed_steps: row.get(7)?,
+                revision: row.get(7)?,
+                completed_steps: row.get(8)?,
                 tags: Vec::new(),
             })
         })
@@ -171,21 +186,24 @@ pub async fn course_delete(
 ) -> Result<(), String> {
     // Collect paths and delete from DB while holding the lock.
     // Release lock BEFORE filesystem I/O.
-    let local_path = {
+    let row = {
         let conn = db.0.lock();
-        let path: Option<String> = conn
+        let row: Option<(String, String)> = conn
             .query_row(
-                "SELECT local_path FROM course WHERE id = ?1",
+                "SELECT source_url, local_path FROM course WHERE id = ?1",
                 params![&id],
-                |row| row.get(0),
+                |row| Ok((row.get(0)?, row.get(1)?)),
             )
             .ok();
         conn.execute("DELETE FROM course WHERE id = ?1", params![&id])
             .map_err(|e| format!("Failed to delete course: {e}"))?;
-        path
+        row
     };
 
-    if let Some(path) = local_path {
+    // Linked courses are the author's working tree — only unregister them.
+    if let Some((source_url, path)) = row
+        && !is_linked(&source_url)
+    {
         let _ = std::fs::remove_dir_all(&path);
     }
 
@@ -205,24 +223,44 @@ pub async fn course_get(db: State<'_, Db>, id: String) -> Result<CourseRecord, S
     read_course_row(&conn, &id)
 }
 
+/// Resolves the variant for `locale`, or the preferred-locale setting when
+/// it's omitted, falling back to the base text per field.
 #[tauri::command]
-pub async fn course_manifest(db: State<'_, Db>, id: String) -> Result<CourseManifest, String> {
-    let local_path = {
+pub async fn course_manifest(
+    db: State<'_, Db>,
+    id: String,
+    locale: Option<String>,
+) -> Result<CourseManifest, String> {
+    let (local_path, stored_hash) = {
         let conn = db.0.lock();
         conn.query_row(
-            "SELECT local_path FROM course WHERE id = ?1",
+            "SELECT local_path, manifest_hash FROM course WHERE id = ?1",
             params![&id],
-            |row| row.get::<_, String>(0),
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
         )
         .map_err(|e| format!("Course not found: {e}"))?
     };
 
-    let manifest_path = std::path::Path::new(&local_path).join("handhold.yaml");
+    let manifest_path = Path::new(&local_path).join("handhold.yaml");
     let content = std::fs::read_to_string(&manifest_path)
         .map_err(|e| format!("Failed to read manifest: {e}"))?;
-    let manifest: Manifest =
-        serde_yml::from_str(&content).map_err(|e| format!("Failed to parse manifest: {e}"))?;
+    let mut manifest =
+        load_manifest(&content).map_err(|e| format!("Failed to parse manifest: {e}"))?;
+
+    // Linked courses live outside the watched directory, so their manifest
+    // can change without a sync. Catch up the same way `course_sync` would
+    // before the frontend indexes into this step list.
+    let manifest_hash = hash_id(&content);
+    if stored_hash.as_deref() != Some(manifest_hash.as_str()) {
+        let mut conn = db.0.lock();
+        refresh_course(&mut conn, &id, &manifest, &manifest_hash)?;
+    }
 
+    localize(
+        &mut manifest,
+        Path::new(&local_path),
+        preferred_locale(locale).as_deref(),
+    );
     Ok(manifest.into_public())
 }
 
@@ -231,6 +269,7 @@ pub async fn course_read_step(
     db: State<'_, Db>,
     id: String,
     step_path: String,
+    locale: Option<String>,
 ) -> Result<String, String> {
     let local_path = {
         let conn = db.0.lock();
@@ -242,7 +281,12 @@ pub async fn course_read_step(
         .map_err(|e| format!("Course not found: {e}"))?
     };
 
-    let resolved = std::path::Path::new(&local_path).join(&step_path);
+    let step_path = localized_step_path(
+        Path::new(&local_path),
+        &step_path,
+        preferred_locale(locale).as_deref(),
+    );
+    let resolved = Path::new(&local_path).join(&step_path);
 
     if resolved.is_dir() {
         let mut entries: Vec<_> = std::fs::read_dir(&resolved)
@@ -273,6 +317,7 @@ pub async fn course_read_lab(
     db: State<'_, Db>,
     id: String,
     step_path: String,
+    locale: Option<String>,
 ) -> Result<LabData, String> {
     let local_path = {
         let conn = db.0.lock();
@@ -284,6 +329,11 @@ pub async fn course_read_lab(
         .map_err(|e| format!("Course not found: {e}"))?
     };
 
+    let step_path = localized_step_path(
+        Path::new(&local_path),
+        &step_path,
+        preferred_locale(locale).as_deref(),
+    );
     let lab_dir = PathBuf::from(&local_path).join(&step_path);
     if !lab_dir.is_dir() {
         return Err(format!("Lab directory not found: {step_path}"));
@@ -0,0 +1,92 @@
+use std::fmt;
+
+use super::types::Manifest;
+
+/// Newest `schemaVersion` this app reads. Manifests without the field are
+/// version 1, the format that predates versioning.
+///
+/// | Version | Adds |
+/// |---------|------|
+/// | 2 | `chapters:`; per-step `optional`, `durationMinutes`, `objectives` |
+/// | 3 | course-level `requires:` and `freeNavigation`; per-step `requires:` |
+/// | 4 | `locales:` translations |
+///
+/// Every field added so far is optional and defaults to the older
+/// behaviour, so an older tree already parses as the current model.
+pub(super) const SCHEMA_VERSION: u32 = 4;
+
+/// Rewrites a manifest tree from the previous version, in place.
+type Migration = fn(&mut serde_yml::Value);
+
+/// Versions that rename or reshape fields, keyed by the version each
+/// migration reaches and applied in order. Versions that only add
+/// optional fields have no entry.
+const MIGRATIONS: &[(u32, Migration)] = &[];
+
+pub(super) enum ManifestError {
+    /// Unparseable YAML, or a shape the model rejects. `line` is 1-based.
+    Invalid { reason: String, line: Option<usize> },
+    /// Written for a newer app. Carries the manifest's `schemaVersion`.
+    TooNew(u32),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::Invalid { reason, .. } => f.write_str(reason),
+            ManifestError::TooNew(version) => write!(
+                f,
+                "Manifest schema {version} needs a newer Handhold (this one reads up to {SCHEMA_VERSION})"
+            ),
+        }
+    }
+}
+
+fn invalid(e: serde_yml::Error) -> ManifestError {
+    ManifestError::Invalid {
+        line: e.location().map(|l| l.line()),
+        reason: e.to_string(),
+    }
+}
+
+/// Parses `handhold.yaml` at any supported schema version into the current model.
+pub(super) fn load_manifest(text: &str) -> Result<Manifest, ManifestError> {
+    let mut value: serde_yml::Value = serde_yml::from_str(text).map_err(invalid)?;
+    let version = match value.get("schemaVersion") {
+        None => 1,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v >= 1)
+            .ok_or_else(|| ManifestError::Invalid {
+                reason: "schemaVersion must be a positive integer".to_string(),
+                line: line_of(text, "schemaVersion"),
+            })?,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(ManifestError::TooNew(version));
+    }
+
+    let original = value.clone();
+    for (_, migrate) in MIGRATIONS.iter().filter(|(to, _)| *to > version) {
+        migrate(&mut value);
+    }
+    let mut manifest: Manifest = if value == original {
+        // Nothing rewritten: parse the text itself so errors keep their line numbers.
+        serde_yml::from_str(text).map_err(invalid)?
+    } else {
+        serde_yml::from_value(value).map_err(invalid)?
+    };
+    manifest
+        .flatten_chapters()
+        .map_err(|reason| ManifestError::Invalid {
+            reason,
+            line: line_of(text, "chapters:"),
+        })?;
+    manifest.assign_step_ids();
+    Ok(manifest)
+}
+
+fn line_of(text: &str, needle: &str) -> Option<usize> {
+    text.lines().position(|l| l.contains(needle)).map(|i| i + 1)
+}
@@ -1,11 +1,43 @@
 use sha2::{Digest, Sha256};
 
+use crate::settings::{ForgeHost, ForgeKind};
+
+/// Forge variants carry `pin`, the ref the user asked for: an `@ref` suffix
+/// or a ref named in the route (`/tree/dev/...`). `branch` starts out as the
+/// requested ref and is replaced by the resolved commit SHA before anything
+/// is downloaded.
 pub(super) enum CourseSource {
     GitHub {
         owner: String,
         repo: String,
         branch: String,
         path: String,
+        pin: Option<String>,
+    },
+    /// `project` is the full namespace path — GitLab allows nested subgroups.
+    GitLab {
+        host: String,
+        project: String,
+        branch: String,
+        path: String,
+        pin: Option<String>,
+    },
+    /// Gitea and Forgejo share a URL layout.
+    Gitea {
+        host: String,
+        owner: String,
+        repo: String,
+        branch: String,
+        path: String,
+        pin: Option<String>,
+    },
+    Bitbucket {
+        host: String,
+        owner: String,
+        repo: String,
+        branch: String,
+        path: String,
+        pin: Option<String>,
     },
     Http {
         manifest_url: String,
@@ -13,6 +45,14 @@ pub(super) enum CourseSource {
     },
 }
 
+/// Forges recognised without any configuration.
+const BUILTIN_FORGES: &[(&str, ForgeKind)] = &[
+    ("gitlab.com", ForgeKind::Gitlab),
+    ("codeberg.org", ForgeKind::Gitea),
+    ("gitea.com", ForgeKind::Gitea),
+    ("bitbucket.org", ForgeKind::Bitbucket),
+];
+
 pub(super) fn hash_id(input: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(input.as_bytes());
@@ -20,18 +60,17 @@ pub(super) fn hash_id(input: &str) -> String {
     format!("{:x}", hash)[..16].to_string()
 }
 
+/// Pinned and unpinned imports of the same course share an id, so the
+/// same course can't be installed twice at different revisions.
 pub(super) fn source_id(source: &CourseSource) -> String {
-    match source {
-        CourseSource::GitHub {
-            owner, repo, path, ..
-        } => {
-            if path.is_empty() {
-                hash_id(&format!("https://github.com/{owner}/{repo}"))
-            } else {
-                hash_id(&format!("https://github.com/{owner}/{repo}/{path}"))
-            }
-        }
-        CourseSource::Http { manifest_url, .. } => hash_id(manifest_url),
+    hash_id(&unpinned_source_url(source))
+}
+
+fn with_subpath(base: String, path: &str) -> String {
+    if path.is_empty() {
+        base
+    } else {
+        format!("{base}/{path}")
     }
 }
 
@@ -42,6 +81,7 @@ pub(super) fn manifest_url(source: &CourseSource) -> String {
             repo,
             branch,
             path,
+            ..
         } => {
             if path.is_empty() {
                 format!("https://raw.githubusercontent.com/{owner}/{repo}/{branch}/handhold.yaml")
@@ -51,11 +91,261 @@ pub(super) fn manifest_url(source: &CourseSource) -> String {
                 )
             }
         }
+        CourseSource::GitLab {
+            host,
+            project,
+            branch,
+            path,
+            ..
+        } => {
+            let base = format!("https://{host}/{project}/-/raw/{branch}");
+            format!("{}/handhold.yaml", with_subpath(base, path))
+        }
+        // The legacy `raw/{ref}` route accepts branches, tags and commits alike.
+        CourseSource::Gitea {
+            host,
+            owner,
+            repo,
+            branch,
+            path,
+            ..
+        }
+        | CourseSource::Bitbucket {
+            host,
+            owner,
+            repo,
+            branch,
+            path,
+            ..
+        } => {
+            let base = format!("https://{host}/{owner}/{repo}/raw/{branch}");
+            format!("{}/handhold.yaml", with_subpath(base, path))
+        }
         CourseSource::Http { manifest_url, .. } => manifest_url.clone(),
     }
 }
 
+/// Tarball URL for forge sources. `None` for plain HTTP, which is fetched file by file.
+pub(super) fn archive_url(source: &CourseSource) -> Option<String> {
+    match source {
+        CourseSource::GitHub {
+            owner,
+            repo,
+            branch,
+            ..
+        } => Some(format!(
+            "https://github.com/{owner}/{repo}/archive/{branch}.tar.gz"
+        )),
+        CourseSource::GitLab {
+            host,
+            project,
+            branch,
+            ..
+        } => {
+            let name = project.rsplit('/').next().unwrap_or(project);
+            let file_ref = branch.replace('/', "-");
+            Some(format!(
+                "https://{host}/{project}/-/archive/{branch}/{name}-{file_ref}.tar.gz"
+            ))
+        }
+        CourseSource::Gitea {
+            host,
+            owner,
+            repo,
+            branch,
+            ..
+        } => Some(format!(
+            "https://{host}/{owner}/{repo}/archive/{branch}.tar.gz"
+        )),
+        CourseSource::Bitbucket {
+            host,
+            owner,
+            repo,
+            branch,
+            ..
+        } => Some(format!("https://{host}/{owner}/{repo}/get/{branch}.tar.gz")),
+        CourseSource::Http { .. } => None,
+    }
+}
+
+/// Manifest URL for a source fetched with credentials. Forge web routes
+/// don't accept API tokens for private repos, so GitLab and Gitea go
+/// through their file APIs. GitHub raw and Bitbucket raw take the token as-is.
+pub(super) fn private_manifest_url(source: &CourseSource) -> String {
+    match source {
+        CourseSource::GitLab {
+            host,
+            project,
+            branch,
+            path,
+            ..
+        } => {
+            let project_id = project.replace('/', "%2F");
+            let file = if path.is_empty() {
+                "handhold.yaml".to_string()
+            } else {
+                format!("{path}/handhold.yaml").replace('/', "%2F")
+            };
+            format!(
+                "https://{host}/api/v4/projects/{project_id}/repository/files/{file}/raw?ref={branch}"
+            )
+        }
+        CourseSource::Gitea {
+            host,
+            owner,
+            repo,
+            branch,
+            path,
+            ..
+        } => {
+            let base = format!("https://{host}/api/v1/repos/{owner}/{repo}/raw");
+            format!("{}/handhold.yaml?ref={branch}", with_subpath(base, path))
+        }
+        _ => manifest_url(source),
+    }
+}
+
+/// Tarball URL for a source fetched with credentials, via each forge's
+/// archive API. Same layout as [`archive_url`]: one top-level directory.
+pub(super) fn private_archive_url(source: &CourseSource) -> Option<String> {
+    match source {
+        CourseSource::GitHub {
+            owner,
+            repo,
+            branch,
+            ..
+        } => Some(format!(
+            "https://api.github.com/repos/{owner}/{repo}/tarball/{branch}"
+        )),
+        CourseSource::GitLab {
+            host,
+            project,
+            branch,
+            ..
+        } => {
+            let project_id = project.replace('/', "%2F");
+            Some(format!(
+                "https://{host}/api/v4/projects/{project_id}/repository/archive.tar.gz?sha={branch}"
+            ))
+        }
+        CourseSource::Gitea {
+            host,
+            owner,
+            repo,
+            branch,
+            ..
+        } => Some(format!(
+            "https://{host}/api/v1/repos/{owner}/{repo}/archive/{branch}.tar.gz"
+        )),
+        _ => archive_url(source),
+    }
+}
+
+/// Directory inside the repository archive that holds the course.
+pub(super) fn subpath(source: &CourseSource) -> &str {
+    match source {
+        CourseSource::GitHub { path, .. }
+        | CourseSource::GitLab { path, .. }
+        | CourseSource::Gitea { path, .. }
+        | CourseSource::Bitbucket { path, .. } => path,
+        CourseSource::Http { .. } => "",
+    }
+}
+
+/// Gitea has no symbolic `HEAD` ref in raw or archive URLs, so a bare repo
+/// URL needs the default branch looked up first. Returns the API endpoint
+/// whose `default_branch` field answers that, or `None` when no lookup is needed.
+pub(super) fn default_branch_api(source: &CourseSource) -> Option<String> {
+    match source {
+        CourseSource::Gitea {
+            host,
+            owner,
+            repo,
+            branch,
+            ..
+        } if branch == "HEAD" => Some(format!("https://{host}/api/v1/repos/{owner}/{repo}")),
+        _ => None,
+    }
+}
+
+/// API endpoint that resolves the current ref to a commit, plus the JSON
+/// pointer to the SHA in its response. `None` for plain HTTP sources.
+pub(super) fn commit_api(source: &CourseSource) -> Option<(String, &'static str)> {
+    match source {
+        CourseSource::GitHub {
+            owner,
+            repo,
+            branch,
+            ..
+        } => Some((
+            format!("https://api.github.com/repos/{owner}/{repo}/commits/{branch}"),
+            "/sha",
+        )),
+        CourseSource::GitLab {
+            host,
+            project,
+            branch,
+            ..
+        } => {
+            let project_id = project.replace('/', "%2F");
+            Some((
+                format!("https://{host}/api/v4/projects/{project_id}/repository/commits/{branch}"),
+                "/id",
+            ))
+        }
+        CourseSource::Gitea {
+            host,
+            owner,
+            repo,
+            branch,
+            ..
+        } => Some((
+            format!("https://{host}/api/v1/repos/{owner}/{repo}/commits?sha={branch}&limit=1"),
+            "/0/sha",
+        )),
+        CourseSource::Bitbucket {
+            owner,
+            repo,
+            branch,
+            ..
+        } => Some((
+            format!("https://api.bitbucket.org/2.0/repositories/{owner}/{repo}/commit/{branch}"),
+            "/hash",
+        )),
+        CourseSource::Http { .. } => None,
+    }
+}
+
+pub(super) fn set_branch(source: &mut CourseSource, resolved: String) {
+    match source {
+        CourseSource::GitHub { branch, .. }
+        | CourseSource::GitLab { branch, .. }
+        | CourseSource::Gitea { branch, .. }
+        | CourseSource::Bitbucket { branch, .. } => *branch = resolved,
+        CourseSource::Http { .. } => {}
+    }
+}
+
+pub(super) fn pin(source: &CourseSource) -> Option<&str> {
+    match source {
+        CourseSource::GitHub { pin, .. }
+        | CourseSource::GitLab { pin, .. }
+        | CourseSource::Gitea { pin, .. }
+        | CourseSource::Bitbucket { pin, .. } => pin.as_deref(),
+        CourseSource::Http { .. } => None,
+    }
+}
+
+/// Canonical URL plus any pin as an `@ref` suffix, so updates keep fetching the same ref.
 pub(super) fn canonical_source_url(source: &CourseSource) -> String {
+    let base = unpinned_source_url(source);
+    match pin(source) {
+        Some(pin) => format!("{base}@{pin}"),
+        None => base,
+    }
+}
+
+fn unpinned_source_url(source: &CourseSource) -> String {
     match source {
         CourseSource::GitHub {
             owner, repo, path, ..
@@ -66,12 +356,191 @@ pub(super) fn canonical_source_url(source: &CourseSource) -> String {
                 format!("https://github.com/{owner}/{repo}/{path}")
             }
         }
+        // Without the `/-/` separator a subpath is indistinguishable from a subgroup.
+        CourseSource::GitLab {
+            host,
+            project,
+            path,
+            ..
+        } => {
+            if path.is_empty() {
+                format!("https://{host}/{project}")
+            } else {
+                format!("https://{host}/{project}/-/tree/HEAD/{path}")
+            }
+        }
+        CourseSource::Gitea {
+            host,
+            owner,
+            repo,
+            path,
+            ..
+        }
+        | CourseSource::Bitbucket {
+            host,
+            owner,
+            repo,
+            path,
+            ..
+        } => with_subpath(format!("https://{host}/{owner}/{repo}"), path),
         CourseSource::Http { manifest_url, .. } => manifest_url.clone(),
     }
 }
 
-pub(super) fn parse_source_url(url: &str) -> Option<CourseSource> {
+/// Joins the remaining segments into a course subpath, dropping a trailing `handhold.yaml`.
+fn course_path(remaining: &[&str]) -> String {
+    if remaining.last().is_some_and(|s| *s == "handhold.yaml") {
+        remaining[..remaining.len() - 1].join("/")
+    } else {
+        remaining.join("/")
+    }
+}
+
+fn forge_kind(host: &str, forges: &[ForgeHost]) -> Option<ForgeKind> {
+    let kind = forges
+        .iter()
+        .find(|f| f.host.eq_ignore_ascii_case(host))
+        .map(|f| f.kind)
+        .or_else(|| {
+            BUILTIN_FORGES
+                .iter()
+                .find(|(h, _)| *h == host)
+                .map(|(_, kind)| *kind)
+        })?;
+    // Self-hosted Bitbucket (Server, Data Center) has none of bitbucket.org's
+    // routes, so a custom entry for it names no course.
+    if kind == ForgeKind::Bitbucket && host != "bitbucket.org" {
+        return None;
+    }
+    Some(kind)
+}
+
+/// `/{project...}[/-/(tree|blob|raw)/{branch}/{path...}]`
+fn parse_gitlab(host: &str, segments: &[&str]) -> Option<CourseSource> {
+    let marker = segments.iter().position(|s| *s == "-");
+    let project_segments = &segments[..marker.unwrap_or(segments.len())];
+    if project_segments.len() < 2 {
+        return None;
+    }
+    let mut project = project_segments.join("/");
+    if let Some(stripped) = project.strip_suffix(".git") {
+        project = stripped.to_string();
+    }
+
+    let (branch, path) = match marker.map(|i| &segments[i + 1..]) {
+        Some([kind, branch, rest @ ..]) if matches!(*kind, "tree" | "blob" | "raw") => {
+            (branch.to_string(), course_path(rest))
+        }
+        _ => ("HEAD".to_string(), String::new()),
+    };
+
+    Some(CourseSource::GitLab {
+        host: host.to_string(),
+        project,
+        branch,
+        path,
+        pin: None,
+    })
+}
+
+/// Gitea: `/{owner}/{repo}[/(src|raw)/(branch|tag|commit)/{ref}/{path...}]`
+/// Bitbucket: `/{owner}/{repo}[/(src|raw)/{ref}/{path...}]`
+/// Any other trailing segments are taken as the course subpath, so
+/// `canonical_source_url` output parses back to the same source.
+fn parse_owner_repo(host: &str, kind: ForgeKind, segments: &[&str]) -> Option<CourseSource> {
+    let [owner, repo, rest @ ..] = segments else {
+        return None;
+    };
+    let owner = owner.to_string();
+    let repo = repo.trim_end_matches(".git").to_string();
+    if owner.is_empty() || repo.is_empty() {
+        return None;
+    }
+
+    let (branch, path) = match (kind, rest) {
+        (
+            ForgeKind::Gitea,
+            [
+                "src" | "raw",
+                "branch" | "tag" | "commit",
+                branch,
+                rest @ ..,
+            ],
+        )
+        | (ForgeKind::Gitea | ForgeKind::Bitbucket, ["src" | "raw", branch, rest @ ..]) => {
+            (branch.to_string(), course_path(rest))
+        }
+        _ => ("HEAD".to_string(), course_path(rest)),
+    };
+
+    let host = host.to_string();
+    Some(match kind {
+        ForgeKind::Bitbucket => CourseSource::Bitbucket {
+            host,
+            owner,
+            repo,
+            branch,
+            path,
+            pin: None,
+        },
+        _ => CourseSource::Gitea {
+            host,
+            owner,
+            repo,
+            branch,
+            path,
+            pin: None,
+        },
+    })
+}
+
+/// Parses a course URL. A trailing `@ref` on the last path segment
+/// (`https://github.com/o/r@v1.2`) pins the import to a tag, branch or SHA.
+pub(super) fn parse_source_url(url: &str, forges: &[ForgeHost]) -> Option<CourseSource> {
     let url = url.trim().trim_end_matches('/');
+    let last_segment_start = url.rfind('/').map_or(0, |i| i + 1);
+    let Some(at) = url[last_segment_start..].rfind('@') else {
+        return parse_unpinned(url, forges).map(pin_named_ref);
+    };
+    let (base, requested) = (
+        &url[..last_segment_start + at],
+        &url[last_segment_start + at + 1..],
+    );
+    if requested.is_empty() {
+        return None;
+    }
+
+    let mut source = parse_unpinned(base, forges)?;
+    set_branch(&mut source, requested.to_string());
+    match &mut source {
+        CourseSource::GitHub { pin, .. }
+        | CourseSource::GitLab { pin, .. }
+        | CourseSource::Gitea { pin, .. }
+        | CourseSource::Bitbucket { pin, .. } => *pin = Some(requested.to_string()),
+        // Plain HTTP has no refs to pin.
+        CourseSource::Http { .. } => return None,
+    }
+    Some(source)
+}
+
+/// Keeps a ref named in the route as the pin. Without it the stored URL
+/// would drop the ref and updates would follow the default branch.
+fn pin_named_ref(mut source: CourseSource) -> CourseSource {
+    match &mut source {
+        CourseSource::GitHub { branch, pin, .. }
+        | CourseSource::GitLab { branch, pin, .. }
+        | CourseSource::Gitea { branch, pin, .. }
+        | CourseSource::Bitbucket { branch, pin, .. }
+            if branch != "HEAD" =>
+        {
+            *pin = Some(branch.clone());
+        }
+        _ => {}
+    }
+    source
+}
+
+fn parse_unpinned(url: &str, forges: &[ForgeHost]) -> Option<CourseSource> {
     let parsed = reqwest::Url::parse(url).ok()?;
     let host = parsed.host_str()?;
     let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
@@ -83,17 +552,13 @@ pub(super) fn parse_source_url(url: &str) -> Option<CourseSource> {
         let owner = segments[0].to_string();
         let repo = segments[1].to_string();
         let branch = segments[2].to_string();
-        let remaining: Vec<&str> = segments[3..].to_vec();
-        let path = if remaining.last().is_some_and(|s| *s == "handhold.yaml") {
-            remaining[..remaining.len() - 1].join("/")
-        } else {
-            remaining.join("/")
-        };
+        let path = course_path(&segments[3..]);
         return Some(CourseSource::GitHub {
             owner,
             repo,
             branch,
             path,
+            pin: None,
         });
     }
 
@@ -113,33 +578,43 @@ pub(super) fn parse_source_url(url: &str) -> Option<CourseSource> {
                 repo,
                 branch: "HEAD".to_string(),
                 path: String::new(),
+                pin: None,
             });
         }
 
         if segments.len() >= 4 && (segments[2] == "blob" || segments[2] == "tree") {
             let branch = segments[3].to_string();
-            let remaining: Vec<&str> = segments[4..].to_vec();
-            let path = if remaining.last().is_some_and(|s| *s == "handhold.yaml") {
-                remaining[..remaining.len() - 1].join("/")
-            } else {
-                remaining.join("/")
-            };
+            let path = course_path(&segments[4..]);
             return Some(CourseSource::GitHub {
                 owner,
                 repo,
                 branch,
                 path,
+                pin: None,
             });
         }
 
+        // `canonical_source_url` form: trailing segments are the subpath.
         return Some(CourseSource::GitHub {
             owner,
             repo,
             branch: "HEAD".to_string(),
-            path: String::new(),
+            path: course_path(&segments[2..]),
+            pin: None,
         });
     }
 
+    // Host ports matter for self-hosted forges (`git.corp:8443`).
+    let authority = match parsed.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    };
+    match forge_kind(&authority, forges).or_else(|| forge_kind(host, forges)) {
+        Some(ForgeKind::Gitlab) => return parse_gitlab(&authority, &segments),
+        Some(kind) => return parse_owner_repo(&authority, kind, &segments),
+        None => {}
+    }
+
     if url.ends_with("/handhold.yaml") {
         let base_url = url.trim_end_matches("handhold.yaml").to_string();
         return Some(CourseSource::Http {
@@ -150,3 +625,160 @@ pub(super) fn parse_source_url(url: &str) -> Option<CourseSource> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forges() -> Vec<ForgeHost> {
+        vec![
+            ForgeHost {
+                host: "git.corp:8443".to_string(),
+                kind: ForgeKind::Gitlab,
+            },
+            ForgeHost {
+                host: "git.example.com".to_string(),
+                kind: ForgeKind::Gitea,
+            },
+            ForgeHost {
+                host: "bitbucket.corp".to_string(),
+                kind: ForgeKind::Bitbucket,
+            },
+        ]
+    }
+
+    /// Input URL, then the manifest URL and canonical URL it should produce.
+    const PARSES: &[(&str, &str, &str)] = &[
+        (
+            "https://github.com/o/r",
+            "https://raw.githubusercontent.com/o/r/HEAD/handhold.yaml",
+            "https://github.com/o/r",
+        ),
+        (
+            "https://github.com/o/r.git/",
+            "https://raw.githubusercontent.com/o/r/HEAD/handhold.yaml",
+            "https://github.com/o/r",
+        ),
+        (
+            "https://github.com/o/r/tree/dev/courses/intro",
+            "https://raw.githubusercontent.com/o/r/dev/courses/intro/handhold.yaml",
+            "https://github.com/o/r/courses/intro@dev",
+        ),
+        (
+            "https://github.com/o/r/blob/main/intro/handhold.yaml",
+            "https://raw.githubusercontent.com/o/r/main/intro/handhold.yaml",
+            "https://github.com/o/r/intro@main",
+        ),
+        (
+            "https://raw.githubusercontent.com/o/r/main/handhold.yaml",
+            "https://raw.githubusercontent.com/o/r/main/handhold.yaml",
+            "https://github.com/o/r@main",
+        ),
+        (
+            "https://github.com/o/r@v1.2",
+            "https://raw.githubusercontent.com/o/r/v1.2/handhold.yaml",
+            "https://github.com/o/r@v1.2",
+        ),
+        (
+            "https://gitlab.com/group/sub/proj",
+            "https://gitlab.com/group/sub/proj/-/raw/HEAD/handhold.yaml",
+            "https://gitlab.com/group/sub/proj",
+        ),
+        (
+            "https://gitlab.com/group/sub/proj/-/tree/main/intro",
+            "https://gitlab.com/group/sub/proj/-/raw/main/intro/handhold.yaml",
+            "https://gitlab.com/group/sub/proj/-/tree/HEAD/intro@main",
+        ),
+        (
+            "https://gitlab.com/group/proj.git@v2",
+            "https://gitlab.com/group/proj/-/raw/v2/handhold.yaml",
+            "https://gitlab.com/group/proj@v2",
+        ),
+        (
+            "https://git.corp:8443/team/proj/-/blob/dev/handhold.yaml",
+            "https://git.corp:8443/team/proj/-/raw/dev/handhold.yaml",
+            "https://git.corp:8443/team/proj@dev",
+        ),
+        (
+            "https://codeberg.org/o/r/src/branch/main/intro",
+            "https://codeberg.org/o/r/raw/main/intro/handhold.yaml",
+            "https://codeberg.org/o/r/intro@main",
+        ),
+        (
+            "https://git.example.com/o/r",
+            "https://git.example.com/o/r/raw/HEAD/handhold.yaml",
+            "https://git.example.com/o/r",
+        ),
+        (
+            "https://git.example.com/o/r/src/tag/v1/intro@v1",
+            "https://git.example.com/o/r/raw/v1/intro/handhold.yaml",
+            "https://git.example.com/o/r/intro@v1",
+        ),
+        (
+            "https://bitbucket.org/o/r/src/main/intro",
+            "https://bitbucket.org/o/r/raw/main/intro/handhold.yaml",
+            "https://bitbucket.org/o/r/intro@main",
+        ),
+        (
+            "https://example.com/courses/intro/handhold.yaml",
+            "https://example.com/courses/intro/handhold.yaml",
+            "https://example.com/courses/intro/handhold.yaml",
+        ),
+    ];
+
+    #[test]
+    fn parses_course_urls() {
+        let forges = forges();
+        for (url, manifest, canonical) in PARSES {
+            let source = parse_source_url(url, &forges).unwrap_or_else(|| panic!("{url}"));
+            assert_eq!(manifest_url(&source), *manifest, "{url}");
+            assert_eq!(canonical_source_url(&source), *canonical, "{url}");
+        }
+    }
+
+    #[test]
+    fn canonical_urls_parse_back_to_the_same_source() {
+        let forges = forges();
+        for (url, _, _) in PARSES {
+            let source = parse_source_url(url, &forges).unwrap();
+            let canonical = canonical_source_url(&source);
+            let reparsed = parse_source_url(&canonical, &forges)
+                .unwrap_or_else(|| panic!("{url} → {canonical}"));
+            assert_eq!(manifest_url(&reparsed), manifest_url(&source), "{url}");
+            assert_eq!(canonical_source_url(&reparsed), canonical, "{url}");
+            assert_eq!(pin(&reparsed), pin(&source), "{url}");
+            assert_eq!(source_id(&reparsed), source_id(&source), "{url}");
+        }
+    }
+
+    #[test]
+    fn pinned_and_unpinned_imports_share_an_id() {
+        let forges = forges();
+        let id = |url: &str| source_id(&parse_source_url(url, &forges).unwrap());
+        assert_eq!(
+            id("https://github.com/o/r"),
+            id("https://github.com/o/r@v1")
+        );
+        assert_eq!(
+            id("https://gitlab.com/g/p/-/tree/dev/intro"),
+            id("https://gitlab.com/g/p/-/tree/HEAD/intro")
+        );
+    }
+
+    #[test]
+    fn rejects_urls_that_name_no_course() {
+        let forges = forges();
+        for url in [
+            "not a url",
+            "https://github.com/o",
+            "https://github.com/o/r@",
+            "https://gitlab.com/proj",
+            "https://example.com/page",
+            "https://example.com/c/handhold.yaml@v1",
+            "https://bitbucket.corp/o/r",
+            "https://bitbucket.corp/o/r/src/main/intro",
+        ] {
+            assert!(parse_source_url(url, &forges).is_none(), "{url}");
+        }
+    }
+}
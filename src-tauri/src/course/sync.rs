@@ -1,12 +1,15 @@
 use crate::db::Db;
 use crate::paths::courses_dir;
-use rusqlite::params;
-use std::path::PathBuf;
+use rusqlite::{Connection, params};
+use std::path::{Path, PathBuf};
 use tauri::State;
 
+use super::import::{parse_manifest, store_steps};
+use super::local::is_linked;
 use super::now_ms;
+use super::progress::adopt_legacy_progress;
 use super::source::hash_id;
-use super::types::{Manifest, SyncResult};
+use super::types::{Manifest, SyncIssue, SyncResult, SyncedCourse};
 
 #[tauri::command]
 pub async fn courses_dir_path() -> Result<String, String> {
@@ -26,29 +29,77 @@ pub async fn home_dir_path() -> String {
         .into_owned()
 }
 
-#[tauri::command]
-pub async fn course_sync(db: State<'_, Db>) -> Result<SyncResult, String> {
-    let dir = courses_dir();
-    if !dir.exists() {
-        return Ok(SyncResult {
-            added: 0,
-            removed: 0,
-        });
+/// Reads and parses a course folder's manifest. A manifest with no steps
+/// counts as invalid, the same as on import.
+fn read_manifest(dir: &Path) -> Result<(String, Manifest), String> {
+    let manifest_path = dir.join("handhold.yaml");
+    let text = std::fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("Failed to read {}: {e}", manifest_path.display()))?;
+    let manifest = parse_manifest(&text).map_err(|e| e.to_string())?;
+    Ok((text, manifest))
+}
+
+/// Rewrites a registered course's row, tags and steps from its manifest.
+pub(super) fn refresh_course(
+    conn: &mut Connection,
+    id: &str,
+    manifest: &Manifest,
+    manifest_hash: &str,
+) -> Result<(), String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin refresh: {e}"))?;
+    tx.execute(
+        "UPDATE course SET title = ?2, description = ?3, step_count = ?4, manifest_hash = ?5
+         WHERE id = ?1",
+        params![
+            id,
+            &manifest.title,
+            &manifest.description,
+            manifest.steps.len() as i64,
+            manifest_hash
+        ],
+    )
+    .map_err(|e| format!("Failed to update course: {e}"))?;
+    tx.execute("DELETE FROM tag WHERE course_id = ?1", params![id])
+        .map_err(|e| format!("Failed to clear tags: {e}"))?;
+    for tag in &manifest.tags {
+        tx.execute(
+            "INSERT OR IGNORE INTO tag (course_id, name) VALUES (?1, ?2)",
+            params![id, tag],
+        )
+        .map_err(|e| format!("Failed to insert tag: {e}"))?;
     }
+    adopt_legacy_progress(&tx, id, manifest)?;
+    store_steps(&tx, id, manifest)?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit refresh: {e}"))
+}
 
-    let mut added: u32 = 0;
-    let mut removed: u32 = 0;
+/// Registers new folders in the courses directory, refreshes registered
+/// courses whose `handhold.yaml` changed since the last sync, and drops
+/// courses whose folder is gone. Linked courses are refreshed too.
+#[tauri::command]
+pub async fn course_sync(db: State<'_, Db>) -> Result<SyncResult, String> {
+    let mut result = SyncResult::default();
 
-    let entries =
-        std::fs::read_dir(&dir).map_err(|e| format!("Failed to read courses directory: {e}"))?;
+    let dir = courses_dir();
+    let entries: Vec<_> = if dir.exists() {
+        std::fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read courses directory: {e}"))?
+            .flatten()
+            .collect()
+    } else {
+        Vec::new()
+    };
 
-    for entry in entries.flatten() {
+    for entry in entries {
         let path = entry.path();
-        if !path.is_dir() {
+        // Dot-directories are staging areas for in-flight updates.
+        if !path.is_dir() || entry.file_name().to_string_lossy().starts_with('.') {
             continue;
         }
-        let manifest_path = path.join("handhold.yaml");
-        if !manifest_path.exists() {
+        if !path.join("handhold.yaml").exists() {
             continue;
         }
 
@@ -68,26 +119,19 @@ pub async fn course_sync(db: State<'_, Db>) -> Result<SyncResult, String> {
             }
         }
 
-        let manifest_text = match std::fs::read_to_string(&manifest_path) {
-            Ok(t) => t,
-            Err(e) => {
-                eprintln!("[sync] failed to read {}: {e}", manifest_path.display());
+        let (manifest_text, manifest) = match read_manifest(&path) {
+            Ok(read) => read,
+            Err(reason) => {
+                eprintln!("[sync] invalid manifest {local_path}: {reason}");
+                result.invalid.push(SyncIssue {
+                    id: None,
+                    path: local_path,
+                    reason,
+                });
                 continue;
             }
         };
 
-        let manifest: Manifest = match serde_yml::from_str(&manifest_text) {
-            Ok(m) => m,
-            Err(e) => {
-                eprintln!("[sync] invalid manifest {}: {e}", manifest_path.display());
-                continue;
-            }
-        };
-
-        if manifest.steps.is_empty() {
-            continue;
-        }
-
         let dirname = entry.file_name().to_string_lossy().to_string();
         let synthetic_url = format!("local://{dirname}");
         let id = hash_id(&synthetic_url);
@@ -108,9 +152,9 @@ pub async fn course_sync(db: State<'_, Db>) -> Result<SyncResult, String> {
         }
 
         if let Err(e) = conn.execute(
-            "INSERT INTO course (id, source_url, local_path, title, description, step_count, added_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![&id, &synthetic_url, &local_path, &manifest.title, &manifest.description, step_count, now],
+            "INSERT INTO course (id, source_url, local_path, title, description, step_count, added_at, manifest_hash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![&id, &synthetic_url, &local_path, &manifest.title, &manifest.description, step_count, now, hash_id(&manifest_text)],
         ) {
             eprintln!("[sync] failed to insert {dirname}: {e}");
             continue;
@@ -122,33 +166,88 @@ pub async fn course_sync(db: State<'_, Db>) -> Result<SyncResult, String> {
                 params![&id, tag],
             );
         }
+        if let Err(e) = store_steps(&conn, &id, &manifest) {
+            eprintln!("[sync] {dirname}: {e}");
+        }
 
         eprintln!("[sync] registered: {dirname} → {}", manifest.title);
-        added += 1;
+        result.added += 1;
     }
 
     // Collect all course rows, then release the lock before checking
     // the filesystem. This avoids holding the DB lock during I/O.
-    let all_courses: Vec<(String, String)> = {
+    let all_courses: Vec<(String, String, String, Option<String>)> = {
         let conn = db.0.lock();
         let mut stmt = conn
-            .prepare("SELECT id, local_path FROM course")
+            .prepare("SELECT id, source_url, local_path, manifest_hash FROM course")
             .map_err(|e| e.to_string())?;
         stmt.query_map([], |row| {
-            let id: String = row.get(0)?;
-            let path: String = row.get(1)?;
-            Ok((id, path))
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
         })
         .map_err(|e| e.to_string())?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?
     };
 
-    let orphan_ids: Vec<&str> = all_courses
-        .iter()
-        .filter(|(_, path)| !PathBuf::from(path).exists())
-        .map(|(id, _)| id.as_str())
-        .collect();
+    let mut orphan_ids: Vec<&str> = Vec::new();
+    for (id, source_url, local_path, stored_hash) in &all_courses {
+        let path = PathBuf::from(local_path);
+        // A linked folder belongs to the author and may just be on an
+        // unmounted drive or mid-rename. Deleting the row would take the
+        // course's progress with it, so it's reported instead.
+        if !path.exists() && is_linked(source_url) {
+            result.invalid.push(SyncIssue {
+                id: Some(id.clone()),
+                path: local_path.clone(),
+                reason: "Linked folder not found".to_string(),
+            });
+            continue;
+        }
+        if !path.exists() {
+            orphan_ids.push(id);
+            continue;
+        }
+        // Folders without a manifest are left for `course_validate` to explain.
+        let Ok(text) = std::fs::read_to_string(path.join("handhold.yaml")) else {
+            continue;
+        };
+        let manifest_hash = hash_id(&text);
+        if stored_hash.as_deref() == Some(manifest_hash.as_str()) {
+            continue;
+        }
+        let manifest = match parse_manifest(&text) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                let reason = e.to_string();
+                eprintln!("[sync] invalid manifest {local_path}: {reason}");
+                result.invalid.push(SyncIssue {
+                    id: Some(id.clone()),
+                    path: local_path.clone(),
+                    reason,
+                });
+                continue;
+            }
+        };
+
+        let mut conn = db.0.lock();
+        if let Err(reason) = refresh_course(&mut conn, id, &manifest, &manifest_hash) {
+            eprintln!("[sync] failed to refresh {id}: {reason}");
+            result.invalid.push(SyncIssue {
+                id: Some(id.clone()),
+                path: local_path.clone(),
+                reason,
+            });
+            continue;
+        }
+        // Rows from before hashing get a baseline without being reported.
+        if stored_hash.is_some() {
+            eprintln!("[sync] refreshed: {id} → {}", manifest.title);
+            result.updated.push(SyncedCourse {
+                id: id.clone(),
+                title: manifest.title,
+            });
+        }
+    }
 
     if !orphan_ids.is_empty() {
         let conn = db.0.lock();
@@ -156,9 +255,9 @@ pub async fn course_sync(db: State<'_, Db>) -> Result<SyncResult, String> {
             conn.execute("DELETE FROM course WHERE id = ?1", params![id])
                 .map_err(|e| e.to_string())?;
             eprintln!("[sync] removed orphan: {id}");
-            removed += 1;
+            result.removed += 1;
         }
     }
 
-    Ok(SyncResult { added, removed })
+    Ok(result)
 }
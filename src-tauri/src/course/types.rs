@@ -9,7 +9,13 @@ pub struct CourseRecord {
     pub title: String,
     pub description: String,
     pub step_count: i64,
+    /// Steps not marked `optional`. Completion is measured against these.
+    pub required_steps: i64,
     pub added_at: i64,
+    /// Commit SHA the installed content came from. `None` for local,
+    /// linked and plain-HTTP courses.
+    pub revision: Option<String>,
+    /// Completed required steps; finishing an optional step doesn't count.
     pub completed_steps: i64,
     pub tags: Vec<String>,
 }
@@ -17,13 +23,230 @@ pub struct CourseRecord {
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase", tag = "kind")]
 pub enum ImportResult {
-    Ok { course: CourseRecord },
+    Ok {
+        course: CourseRecord,
+    },
     InvalidUrl,
     NotFound,
     NoManifest,
-    BadManifest { reason: String },
+    BadManifest {
+        reason: String,
+    },
     AlreadyExists,
-    DownloadFailed { reason: String },
+    DownloadFailed {
+        reason: String,
+    },
+    /// Offline package is unreadable, missing its header, or too new.
+    BadPackage {
+        reason: String,
+    },
+    /// Checksum mismatch, unlisted or missing file, or bad manifest signature.
+    IntegrityFailed {
+        file: String,
+    },
+    /// The manifest is signed by a publisher not in the local keyring.
+    UntrustedPublisher {
+        publisher: String,
+    },
+    /// Stopped by `course_import_cancel`; nothing was installed.
+    Cancelled,
+    /// `host` wants credentials, or rejected the stored ones.
+    Unauthorized {
+        host: String,
+    },
+    /// `course_validate` found errors in the downloaded course.
+    Invalid {
+        diagnostics: Vec<Diagnostic>,
+    },
+    /// The manifest's `schemaVersion` is newer than this app reads.
+    NeedsNewerApp {
+        required: u32,
+        supported: u32,
+    },
+    /// The request never got a response; `reason` names the cause.
+    NetworkError {
+        reason: String,
+    },
+    /// The forge couldn't resolve the ref to a commit, so nothing was pinned.
+    Unpinned {
+        reason: String,
+    },
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Blocks import: the course would fail when a learner reaches it.
+    Error,
+    Warning,
+}
+
+/// One finding from `course_validate`. `file` is relative to the course root.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub file: String,
+    /// 1-based, when the finding can be pinned to a line.
+    pub line: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Import phases streamed to the import dialog while `course_import` runs.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event")]
+pub enum ImportEvent {
+    Resolving,
+    #[serde(rename_all = "camelCase")]
+    ManifestFetched {
+        title: String,
+        step_count: usize,
+    },
+    /// `total` is `None` when the server doesn't send a Content-Length.
+    Downloaded {
+        bytes: u64,
+        total: Option<u64>,
+    },
+    Extracted {
+        files: usize,
+    },
+    Registering,
+}
+
+/// What an update would change, relative to the installed copy.
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseChanges {
+    /// The source moved to a new revision. Step files may have changed even
+    /// when the manifest diff below is empty.
+    pub content_changed: bool,
+    pub title_changed: bool,
+    pub description_changed: bool,
+    /// Step paths only in the new manifest.
+    pub steps_added: Vec<String>,
+    /// Step paths only in the installed manifest.
+    pub steps_removed: Vec<String>,
+    /// Step paths whose title, kind or (after download) content differ.
+    pub steps_edited: Vec<String>,
+    pub tags_added: Vec<String>,
+    pub tags_removed: Vec<String>,
+}
+
+impl CourseChanges {
+    pub fn is_empty(&self) -> bool {
+        !self.content_changed
+            && !self.title_changed
+            && !self.description_changed
+            && self.steps_added.is_empty()
+            && self.steps_removed.is_empty()
+            && self.steps_edited.is_empty()
+            && self.tags_added.is_empty()
+            && self.tags_removed.is_empty()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum UpdateCheck {
+    UpToDate,
+    Available {
+        changes: CourseChanges,
+    },
+    /// Local and linked courses have no remote to check.
+    Unsupported,
+    NotFound,
+    NoManifest,
+    BadManifest {
+        reason: String,
+    },
+    Unauthorized {
+        host: String,
+    },
+    NeedsNewerApp {
+        required: u32,
+        supported: u32,
+    },
+    /// The request never got a response; `reason` names the cause.
+    NetworkError {
+        reason: String,
+    },
+    /// The forge couldn't resolve the ref to a commit, so nothing was pinned.
+    Unpinned {
+        reason: String,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum UpdateResult {
+    Ok {
+        course: CourseRecord,
+        changes: Box<CourseChanges>,
+    },
+    Unsupported,
+    NotFound,
+    NoManifest,
+    BadManifest {
+        reason: String,
+    },
+    DownloadFailed {
+        reason: String,
+    },
+    IntegrityFailed {
+        file: String,
+    },
+    UntrustedPublisher {
+        publisher: String,
+    },
+    Unauthorized {
+        host: String,
+    },
+    Invalid {
+        diagnostics: Vec<Diagnostic>,
+    },
+    NeedsNewerApp {
+        required: u32,
+        supported: u32,
+    },
+    /// The request never got a response; `reason` names the cause.
+    NetworkError {
+        reason: String,
+    },
+    /// The forge couldn't resolve the ref to a commit, so nothing was pinned.
+    Unpinned {
+        reason: String,
+    },
+}
+
+/// A course advertised by a catalog index, cached for offline browsing.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogEntry {
+    pub source_url: String,
+    /// The index URL or path this entry came from.
+    pub catalog: String,
+    pub title: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    /// Revision the catalog recommends. Installs are pinned to it.
+    pub revision: Option<String>,
+    pub installed: bool,
+}
+
+/// Outcome of refreshing one catalog. A failed catalog keeps its cached entries.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum CatalogRefresh {
+    Ok {
+        catalog: String,
+        courses: usize,
+        /// Entries whose source URL isn't importable.
+        skipped: usize,
+    },
+    Failed {
+        catalog: String,
+        reason: String,
+    },
 }
 
 #[derive(Serialize, Deserialize)]
@@ -41,14 +264,68 @@ pub enum Route {
     },
 }
 
+/// How `progress_import` combines a file with existing progress.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeStrategy {
+    /// Keep local progress and add the file's: earliest completion, furthest slide.
+    Union,
+    /// Replace each matched course's progress with the file's.
+    Overwrite,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum ProgressImport {
+    #[serde(rename_all = "camelCase")]
+    Ok {
+        /// Courses in the file that matched an installed course.
+        courses: u32,
+        unmatched_courses: Vec<UnmatchedCourse>,
+        unmatched_steps: Vec<UnmatchedStep>,
+    },
+    /// Unreadable, not a progress export, or written by a newer app.
+    BadFile { reason: String },
+}
+
+/// A course in the file with no installed counterpart.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmatchedCourse {
+    pub source_url: String,
+    pub title: String,
+}
+
+/// Progress for a step the installed manifest no longer has.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmatchedStep {
+    pub source_url: String,
+    pub step_id: String,
+}
+
+/// Outcome of a `handhold://` link, streamed to the `deep_link_subscribe` channel.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum DeepLinkEvent {
+    /// `app_route` now points here.
+    Navigate { route: Route },
+    /// `handhold://import?url=…`: open the import dialog prefilled, so the
+    /// learner sees the URL and confirms before anything is downloaded.
+    #[serde(rename_all = "camelCase")]
+    ImportRequested { source_url: String },
+    /// The link is malformed or names a course or step that isn't installed.
+    Rejected { url: String, reason: String },
+}
+
 /// Raw dependency entry from handhold.yaml.
 /// install keys are std::env::consts::OS values: "macos", "linux", "windows".
 #[derive(Deserialize)]
-struct ManifestDependency {
-    name: String,
-    check: String,
+pub(super) struct ManifestDependency {
+    pub name: String,
+    pub check: String,
     #[serde(default)]
-    install: std::collections::HashMap<String, String>,
+    pub install: std::collections::HashMap<String, String>,
 }
 
 impl ManifestDependency {
@@ -64,6 +341,37 @@ impl ManifestDependency {
     }
 }
 
+/// Why a step can't be completed yet.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum LockReason {
+    /// An earlier step in this course isn't complete.
+    #[serde(rename_all = "camelCase")]
+    Step { step_index: usize, title: String },
+    /// A required course is installed but has required steps left.
+    #[serde(rename_all = "camelCase")]
+    Course { source_url: String, title: String },
+    /// A required course isn't installed.
+    #[serde(rename_all = "camelCase")]
+    CourseMissing { source_url: String },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepLock {
+    pub step_index: usize,
+    pub reasons: Vec<LockReason>,
+}
+
+/// Gating state for one course. `course` reasons lock every step.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseUnlocks {
+    pub free_navigation: bool,
+    pub course: Vec<LockReason>,
+    pub steps: Vec<StepLock>,
+}
+
 /// Platform-resolved dependency — frontend never sees OS keys.
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -80,18 +388,119 @@ pub(super) struct Manifest {
     pub description: String,
     #[serde(default)]
     pub tags: Vec<String>,
+    /// Every step in course order; indices are the progress key. Chaptered
+    /// manifests have theirs moved here by `flatten_chapters`.
+    #[serde(default)]
     pub steps: Vec<ManifestStep>,
+    /// `chapters:` as written — the alternative to a flat `steps:` list.
+    #[serde(default, rename = "chapters")]
+    pub chapter_groups: Vec<ManifestChapter>,
+    #[serde(skip)]
+    pub chapters: Vec<CourseChapter>,
+    #[serde(default)]
+    pub dependencies: Vec<ManifestDependency>,
+    #[serde(default)]
+    pub integrity: Option<ManifestIntegrity>,
+    /// Courses to finish first, by source URL.
+    #[serde(default)]
+    pub requires: Vec<String>,
+    /// Lets learners complete steps in any order; `requires:` is then advisory.
+    #[serde(default, rename = "freeNavigation")]
+    pub free_navigation: bool,
+    /// Translations keyed by BCP 47 tag. The top-level text is the base
+    /// variant every locale falls back to, field by field.
+    #[serde(default)]
+    pub locales: std::collections::BTreeMap<String, ManifestLocale>,
+    /// Locale `course_manifest` resolved; set by `localize`.
+    #[serde(skip)]
+    pub locale: Option<String>,
+    /// HTTP sources only: a `.tar.gz` of the whole course, absolute or
+    /// relative to the manifest, with the course inside one top-level
+    /// directory. Takes precedence over `handhold-files.json`.
+    #[serde(default)]
+    pub archive: Option<String>,
+}
+
+/// Optional `integrity:` block. `files` maps every course file except
+/// `handhold.yaml` to its hex SHA-256. `signature` is a base64 ed25519
+/// signature by `publisher` over the rest of the manifest.
+#[derive(Deserialize)]
+pub(super) struct ManifestIntegrity {
+    #[serde(default)]
+    pub files: std::collections::BTreeMap<String, String>,
+    pub publisher: Option<String>,
+    pub signature: Option<String>,
+}
+
+/// One `locales:` entry. Anything left out falls back to the base manifest.
+#[derive(Deserialize)]
+pub(super) struct ManifestLocale {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Base chapter title → localized title.
+    #[serde(default)]
+    pub chapters: std::collections::HashMap<String, String>,
+    /// Keyed by step id or base path.
     #[serde(default)]
-    dependencies: Vec<ManifestDependency>,
+    pub steps: std::collections::HashMap<String, LocalizedStep>,
+}
+
+#[derive(Deserialize)]
+pub(super) struct LocalizedStep {
+    pub title: Option<String>,
+    /// Translated copy of the step's files. Progress stays with the step id.
+    pub path: Option<String>,
+    pub objectives: Option<Vec<String>>,
+}
+
+/// One entry of `chapters:`. Its steps become a contiguous run of the
+/// manifest's flat step list.
+#[derive(Deserialize)]
+pub(super) struct ManifestChapter {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub steps: Vec<ManifestStep>,
 }
 
 impl Manifest {
+    /// Moves chapter steps into `steps` and records each chapter's range.
+    /// A manifest lists either `steps` or `chapters`, never both.
+    pub fn flatten_chapters(&mut self) -> Result<(), String> {
+        if self.chapter_groups.is_empty() {
+            return Ok(());
+        }
+        if !self.steps.is_empty() {
+            return Err("Manifest has both steps and chapters; use one or the other".to_string());
+        }
+        for group in std::mem::take(&mut self.chapter_groups) {
+            self.chapters.push(CourseChapter {
+                title: group.title,
+                description: group.description,
+                first_step: self.steps.len(),
+                step_count: group.steps.len(),
+            });
+            self.steps.extend(group.steps);
+        }
+        Ok(())
+    }
+
+    /// Gives every step without an explicit `id` the hash of its path.
+    pub fn assign_step_ids(&mut self) {
+        for step in self.steps.iter_mut().filter(|s| s.id.is_empty()) {
+            step.id = super::source::hash_id(&step.path);
+        }
+    }
+
     pub fn into_public(self) -> CourseManifest {
         CourseManifest {
             title: self.title,
             description: self.description,
             tags: self.tags,
             steps: self.steps,
+            chapters: self.chapters,
+            locale: self.locale,
+            locales: self.locales.into_keys().collect(),
             dependencies: self
                 .dependencies
                 .into_iter()
@@ -101,7 +510,7 @@ impl Manifest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum StepKind {
     Lesson,
@@ -111,9 +520,36 @@ pub enum StepKind {
 #[derive(Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ManifestStep {
+    /// Progress key that survives reordering. Defaults to a hash of `path`,
+    /// so set it explicitly before moving or renaming a step's files.
+    #[serde(default)]
+    pub id: String,
     pub kind: StepKind,
     pub title: String,
     pub path: String,
+    /// Skippable; doesn't count toward course completion.
+    #[serde(default)]
+    pub optional: bool,
+    /// Author's estimate of how long the step takes.
+    #[serde(default)]
+    pub duration_minutes: Option<u32>,
+    /// What the learner should be able to do after the step.
+    #[serde(default)]
+    pub objectives: Vec<String>,
+    /// Steps to complete first, by id or path.
+    #[serde(default)]
+    pub requires: Vec<String>,
+}
+
+/// A titled run of consecutive steps. Empty when the manifest isn't chaptered.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseChapter {
+    pub title: String,
+    pub description: String,
+    /// Index into `CourseManifest::steps` of the chapter's first step.
+    pub first_step: usize,
+    pub step_count: usize,
 }
 
 #[derive(Serialize)]
@@ -123,6 +559,11 @@ pub struct CourseManifest {
     pub description: String,
     pub tags: Vec<String>,
     pub steps: Vec<ManifestStep>,
+    pub chapters: Vec<CourseChapter>,
+    /// Locale the text and step paths were resolved to; `None` is the base.
+    pub locale: Option<String>,
+    /// Every locale the course declares.
+    pub locales: Vec<String>,
     pub dependencies: Vec<CourseDependency>,
 }
 
@@ -133,11 +574,131 @@ pub struct SlidePosition {
     pub slide_count: Option<i64>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteKind {
+    Note,
+    Bookmark,
+    Highlight,
+}
+
+/// Where in its step a note is attached.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum NoteAnchor {
+    /// The step as a whole.
+    Step,
+    #[serde(rename_all = "camelCase")]
+    Slide { slide_id: String },
+    /// A file in the lab workspace, by relative path. Lines count from 1
+    /// and the range is inclusive; no range means the whole file.
+    #[serde(rename_all = "camelCase")]
+    LabFile {
+        path: String,
+        line_start: Option<i64>,
+        line_end: Option<i64>,
+    },
+}
+
+/// A learner-written note, bookmark or highlight.
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
+pub struct Note {
+    pub id: i64,
+    pub course_id: String,
+    pub step_id: String,
+    /// `None` once the step has been removed from the course.
+    pub step_index: Option<i64>,
+    pub kind: NoteKind,
+    pub anchor: NoteAnchor,
+    /// The highlighted text, for highlights.
+    pub quote: Option<String>,
+    pub body: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Learning time and completion history, from `progress_stats`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressStats {
+    /// Courses with any tracked time, most time first.
+    pub courses: Vec<CourseTime>,
+    pub streak: Streak,
+    /// Step completions per week, oldest first. Weeks without any are omitted.
+    pub weekly_completions: Vec<WeekCompletions>,
+    /// Mean active time across completed labs; `None` before the first one.
+    pub average_lab_ms: Option<i64>,
+    /// Completed labs with tracked time, the ones the average covers.
+    pub completed_labs: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseTime {
+    pub course_id: String,
+    pub title: String,
+    pub active_ms: i64,
+    /// In course order; steps no longer in the manifest come last.
+    pub steps: Vec<StepTime>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepTime {
+    pub step_id: String,
+    /// `None` once the step has been removed from the course.
+    pub step_index: Option<i64>,
+    pub kind: StepKind,
+    pub active_ms: i64,
+    pub sessions: u32,
+}
+
+/// Consecutive local calendar days with study time or a completion.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Streak {
+    /// Runs through today, or through yesterday if today has nothing yet.
+    pub current_days: u32,
+    pub longest_days: u32,
+    /// `YYYY-MM-DD`, local time.
+    pub last_active_day: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeekCompletions {
+    /// Monday the week starts on, `YYYY-MM-DD` in local time.
+    pub week_start: String,
+    pub completions: u32,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct SyncResult {
     pub added: u32,
     pub removed: u32,
+    /// Registered courses whose manifest changed and whose metadata was refreshed.
+    pub updated: Vec<SyncedCourse>,
+    /// Folders whose manifest couldn't be read or parsed, and linked folders
+    /// that are missing. Registered ones keep their rows and old metadata.
+    pub invalid: Vec<SyncIssue>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncedCourse {
+    pub id: String,
+    pub title: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncIssue {
+    /// `None` for a folder that was never registered.
+    pub id: Option<String>,
+    pub path: String,
+    pub reason: String,
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -0,0 +1,354 @@
+use crate::db::Db;
+use crate::paths::courses_dir;
+use rusqlite::params;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::Path;
+use tauri::State;
+
+use super::download::Transfer;
+use super::import::{FetchError, download_source, fetch_manifest, parse_manifest, store_steps};
+use super::integrity::{IntegrityError, verify_course};
+use super::progress::adopt_legacy_progress;
+use super::queries::read_course_row;
+use super::schema::SCHEMA_VERSION;
+use super::source::{CourseSource, parse_source_url};
+use super::types::{CourseChanges, Manifest, UpdateCheck, UpdateResult};
+use super::validate::{has_errors, validate_course};
+
+impl From<FetchError> for UpdateCheck {
+    fn from(e: FetchError) -> Self {
+        match e {
+            FetchError::NotFound => UpdateCheck::NotFound,
+            FetchError::NoManifest => UpdateCheck::NoManifest,
+            FetchError::BadManifest(reason) => UpdateCheck::BadManifest { reason },
+            FetchError::Unauthorized(host) => UpdateCheck::Unauthorized { host },
+            FetchError::TooNew(required) => UpdateCheck::NeedsNewerApp {
+                required,
+                supported: SCHEMA_VERSION,
+            },
+            FetchError::Network(reason) => UpdateCheck::NetworkError { reason },
+            FetchError::Unpinned(reason) => UpdateCheck::Unpinned { reason },
+        }
+    }
+}
+
+impl From<FetchError> for UpdateResult {
+    fn from(e: FetchError) -> Self {
+        match e {
+            FetchError::NotFound => UpdateResult::NotFound,
+            FetchError::NoManifest => UpdateResult::NoManifest,
+            FetchError::BadManifest(reason) => UpdateResult::BadManifest { reason },
+            FetchError::Unauthorized(host) => UpdateResult::Unauthorized { host },
+            FetchError::TooNew(required) => UpdateResult::NeedsNewerApp {
+                required,
+                supported: SCHEMA_VERSION,
+            },
+            FetchError::Network(reason) => UpdateResult::NetworkError { reason },
+            FetchError::Unpinned(reason) => UpdateResult::Unpinned { reason },
+        }
+    }
+}
+
+impl From<IntegrityError> for UpdateResult {
+    fn from(e: IntegrityError) -> Self {
+        match e {
+            IntegrityError::File(file) => UpdateResult::IntegrityFailed { file },
+            IntegrityError::UntrustedPublisher(publisher) => {
+                UpdateResult::UntrustedPublisher { publisher }
+            }
+        }
+    }
+}
+
+struct InstalledCourse {
+    source: Option<CourseSource>,
+    local_path: String,
+    revision: Option<String>,
+    manifest: Manifest,
+}
+
+/// Loads the stored row and the manifest on disk. `source` is `None` for
+/// `local://` and `link://` courses, which have no remote to update from.
+fn load_installed(db: &Db, id: &str) -> Result<InstalledCourse, String> {
+    let (source_url, local_path, revision) = {
+        let conn = db.0.lock();
+        conn.query_row(
+            "SELECT source_url, local_path, revision FROM course WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            },
+        )
+        .map_err(|e| format!("Course not found: {e}"))?
+    };
+
+    let manifest_text = std::fs::read_to_string(Path::new(&local_path).join("handhold.yaml"))
+        .map_err(|e| format!("Failed to read manifest: {e}"))?;
+    let manifest = parse_manifest(&manifest_text)
+        .map_err(|e| format!("Failed to parse installed manifest: {e}"))?;
+
+    let forges = crate::settings::current().forges;
+    Ok(InstalledCourse {
+        source: parse_source_url(&source_url, &forges),
+        local_path,
+        revision,
+        manifest,
+    })
+}
+
+/// Manifest-level diff. Steps are matched by path, so a renamed file
+/// reads as one removal plus one addition.
+fn diff_manifests(old: &Manifest, new: &Manifest) -> CourseChanges {
+    let old_paths: HashSet<&str> = old.steps.iter().map(|s| s.path.as_str()).collect();
+    let new_paths: HashSet<&str> = new.steps.iter().map(|s| s.path.as_str()).collect();
+    let old_tags: HashSet<&str> = old.tags.iter().map(String::as_str).collect();
+    let new_tags: HashSet<&str> = new.tags.iter().map(String::as_str).collect();
+
+    let steps_edited = new
+        .steps
+        .iter()
+        .filter(|n| {
+            old.steps
+                .iter()
+                .find(|o| o.path == n.path)
+                .is_some_and(|o| o.title != n.title || o.kind != n.kind)
+        })
+        .map(|s| s.path.clone())
+        .collect();
+
+    CourseChanges {
+        content_changed: false,
+        title_changed: old.title != new.title,
+        description_changed: old.description != new.description,
+        steps_added: new
+            .steps
+            .iter()
+            .filter(|s| !old_paths.contains(s.path.as_str()))
+            .map(|s| s.path.clone())
+            .collect(),
+        steps_removed: old
+            .steps
+            .iter()
+            .filter(|s| !new_paths.contains(s.path.as_str()))
+            .map(|s| s.path.clone())
+            .collect(),
+        steps_edited,
+        tags_added: new
+            .tags
+            .iter()
+            .filter(|t| !old_tags.contains(t.as_str()))
+            .cloned()
+            .collect(),
+        tags_removed: old
+            .tags
+            .iter()
+            .filter(|t| !new_tags.contains(t.as_str()))
+            .cloned()
+            .collect(),
+    }
+}
+
+/// Content hash of a step file or directory tree. Entries are visited in
+/// name order so the digest is independent of filesystem iteration order.
+fn digest_path(path: &Path, hasher: &mut Sha256) -> std::io::Result<()> {
+    if path.is_dir() {
+        let mut entries: Vec<_> = std::fs::read_dir(path)?.collect::<Result<_, _>>()?;
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            hasher.update(entry.file_name().to_string_lossy().as_bytes());
+            digest_path(&entry.path(), hasher)?;
+        }
+    } else {
+        hasher.update(std::fs::read(path)?);
+    }
+    Ok(())
+}
+
+fn content_differs(old: &Path, new: &Path) -> bool {
+    let mut a = Sha256::new();
+    let mut b = Sha256::new();
+    match (digest_path(old, &mut a), digest_path(new, &mut b)) {
+        (Ok(()), Ok(())) => a.finalize() != b.finalize(),
+        _ => true,
+    }
+}
+
+/// Replaces `target` with `staged`, restoring the original if the second
+/// rename fails. The original stays at `backup` until the caller either
+/// removes it or puts it back with `restore_dir`.
+fn swap_dirs(target: &Path, staged: &Path, backup: &Path) -> Result<(), String> {
+    if backup.exists() {
+        std::fs::remove_dir_all(backup)
+            .map_err(|e| format!("Failed to clean backup directory: {e}"))?;
+    }
+    std::fs::rename(target, backup).map_err(|e| format!("Failed to move old course aside: {e}"))?;
+    if let Err(e) = std::fs::rename(staged, target) {
+        let _ = std::fs::rename(backup, target);
+        return Err(format!("Failed to install updated course: {e}"));
+    }
+    Ok(())
+}
+
+/// Undoes `swap_dirs`.
+fn restore_dir(target: &Path, backup: &Path) {
+    let _ = std::fs::remove_dir_all(target);
+    let _ = std::fs::rename(backup, target);
+}
+
+/// Writes the updated manifest's metadata and steps in one transaction.
+fn store_update(
+    db: &Db,
+    id: &str,
+    installed: &Manifest,
+    manifest: &Manifest,
+    revision: Option<&str>,
+) -> Result<(), String> {
+    let mut conn = db.0.lock();
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin update: {e}"))?;
+    tx.execute(
+        "UPDATE course SET title = ?2, description = ?3, step_count = ?4, revision = ?5,
+             manifest_hash = NULL
+         WHERE id = ?1",
+        params![
+            id,
+            &manifest.title,
+            &manifest.description,
+            manifest.steps.len() as i64,
+            revision
+        ],
+    )
+    .map_err(|e| format!("Failed to update course: {e}"))?;
+    tx.execute("DELETE FROM tag WHERE course_id = ?1", params![id])
+        .map_err(|e| format!("Failed to clear tags: {e}"))?;
+    for tag in &manifest.tags {
+        tx.execute(
+            "INSERT OR IGNORE INTO tag (course_id, name) VALUES (?1, ?2)",
+            params![id, tag],
+        )
+        .map_err(|e| format!("Failed to insert tag: {e}"))?;
+    }
+    // Index-keyed progress was recorded against the installed manifest.
+    adopt_legacy_progress(&tx, id, installed)?;
+    store_steps(&tx, id, manifest)?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit update: {e}"))
+}
+
+/// Fetches the remote manifest and reports what an update would change.
+#[tauri::command]
+pub async fn course_check_update(db: State<'_, Db>, id: String) -> Result<UpdateCheck, String> {
+    let installed = load_installed(&db, &id)?;
+    let Some(mut source) = installed.source else {
+        return Ok(UpdateCheck::Unsupported);
+    };
+
+    let fetched = match fetch_manifest(&mut source).await {
+        Ok(fetched) => fetched,
+        Err(e) => return Ok(e.into()),
+    };
+    if fetched.revision.is_some() && fetched.revision == installed.revision {
+        return Ok(UpdateCheck::UpToDate);
+    }
+
+    // A new revision is an update even when handhold.yaml is untouched:
+    // most pushes fix step content. Sources without revisions (plain HTTP)
+    // only have the manifest to compare.
+    let mut changes = diff_manifests(&installed.manifest, &fetched.manifest);
+    changes.content_changed = fetched.revision.is_some();
+    if changes.is_empty() {
+        Ok(UpdateCheck::UpToDate)
+    } else {
+        Ok(UpdateCheck::Available { changes })
+    }
+}
+
+/// Re-downloads a course from its source and swaps it in place.
+/// Progress rows are keyed by course id and survive untouched.
+#[tauri::command]
+pub async fn course_update(db: State<'_, Db>, id: String) -> Result<UpdateResult, String> {
+    let installed = load_installed(&db, &id)?;
+    let Some(mut source) = installed.source else {
+        return Ok(UpdateResult::Unsupported);
+    };
+
+    let fetched = match fetch_manifest(&mut source).await {
+        Ok(fetched) => fetched,
+        Err(e) => return Ok(e.into()),
+    };
+    let manifest = &fetched.manifest;
+
+    // Hidden siblings so `course_sync` never registers a half-finished swap.
+    let staged = courses_dir().join(format!(".{id}.update"));
+    let backup = courses_dir().join(format!(".{id}.old"));
+    if staged.exists() {
+        std::fs::remove_dir_all(&staged)
+            .map_err(|e| format!("Failed to clean staging directory: {e}"))?;
+    }
+    std::fs::create_dir_all(&staged)
+        .map_err(|e| format!("Failed to create staging directory: {e}"))?;
+
+    if let Err(e) = download_source(
+        &source,
+        fetched.revision.as_deref(),
+        &fetched.text,
+        manifest,
+        &staged,
+        &Transfer::silent(),
+    ) {
+        let _ = std::fs::remove_dir_all(&staged);
+        return Ok(UpdateResult::DownloadFailed { reason: e });
+    }
+    if let Err(e) = verify_course(&staged) {
+        let _ = std::fs::remove_dir_all(&staged);
+        return Ok(e.into());
+    }
+    let diagnostics = validate_course(&staged);
+    if has_errors(&diagnostics) {
+        let _ = std::fs::remove_dir_all(&staged);
+        return Ok(UpdateResult::Invalid { diagnostics });
+    }
+
+    let target = Path::new(&installed.local_path);
+    let mut changes = diff_manifests(&installed.manifest, manifest);
+    changes.content_changed = fetched.revision.is_some() && fetched.revision != installed.revision;
+    for step in &manifest.steps {
+        let unchanged_so_far = installed.manifest.steps.iter().any(|s| s.path == step.path)
+            && !changes.steps_edited.contains(&step.path);
+        if unchanged_so_far && content_differs(&target.join(&step.path), &staged.join(&step.path)) {
+            changes.steps_edited.push(step.path.clone());
+        }
+    }
+
+    if let Err(e) = swap_dirs(target, &staged, &backup) {
+        let _ = std::fs::remove_dir_all(&staged);
+        return Ok(UpdateResult::DownloadFailed { reason: e });
+    }
+
+    // The files are live now; if the rows can't follow, put the old ones back
+    // so the database never describes a course that isn't on disk.
+    if let Err(e) = store_update(
+        &db,
+        &id,
+        &installed.manifest,
+        manifest,
+        fetched.revision.as_deref(),
+    ) {
+        restore_dir(target, &backup);
+        return Err(e);
+    }
+    let _ = std::fs::remove_dir_all(&backup);
+
+    let conn = db.0.lock();
+    let course = read_course_row(&conn, &id)?;
+    Ok(UpdateResult::Ok {
+        course,
+        changes: Box::new(changes),
+    })
+}
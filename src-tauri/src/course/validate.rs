@@ -0,0 +1,382 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::download::safe_relative;
+use super::gating::resolve_step;
+use super::schema::{ManifestError, load_manifest};
+use super::types::{Diagnostic, Manifest, RawLabConfig, Severity, StepKind};
+
+/// `install:` keys a dependency may use — `std::env::consts::OS` values.
+const KNOWN_OS: &[&str] = &["macos", "linux", "windows"];
+const MANIFEST: &str = "handhold.yaml";
+
+struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    fn push(&mut self, severity: Severity, file: &str, line: Option<usize>, message: String) {
+        self.0.push(Diagnostic {
+            file: file.to_string(),
+            line,
+            severity,
+            message,
+        });
+    }
+
+    fn error(&mut self, file: &str, line: Option<usize>, message: String) {
+        self.push(Severity::Error, file, line, message);
+    }
+
+    fn warning(&mut self, file: &str, line: Option<usize>, message: String) {
+        self.push(Severity::Warning, file, line, message);
+    }
+}
+
+/// 1-based line of the first line in `text` containing `needle`. The
+/// manifest model carries no spans, so step and dependency findings point
+/// at the line that mentions them.
+fn line_of(text: &str, needle: &str) -> Option<usize> {
+    if needle.is_empty() {
+        return None;
+    }
+    text.lines().position(|l| l.contains(needle)).map(|i| i + 1)
+}
+
+pub(super) fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Severity::Error)
+}
+
+/// Checks a course directory the way the app will use it: the manifest,
+/// every step path, and each lab's instructions, config and workspace dirs.
+pub(super) fn validate_course(dir: &Path) -> Vec<Diagnostic> {
+    let mut out = Diagnostics(Vec::new());
+
+    let Ok(text) = std::fs::read_to_string(dir.join(MANIFEST)) else {
+        out.error(MANIFEST, None, "Missing handhold.yaml".to_string());
+        return out.0;
+    };
+    let manifest = match load_manifest(&text) {
+        Ok(m) => m,
+        Err(ManifestError::Invalid { reason, line }) => {
+            out.error(MANIFEST, line, reason);
+            return out.0;
+        }
+        Err(too_new) => {
+            out.error(
+                MANIFEST,
+                line_of(&text, "schemaVersion"),
+                too_new.to_string(),
+            );
+            return out.0;
+        }
+    };
+
+    if manifest.title.trim().is_empty() {
+        out.error(
+            MANIFEST,
+            line_of(&text, "title:"),
+            "Course title is empty".to_string(),
+        );
+    }
+    if manifest.description.trim().is_empty() {
+        out.warning(
+            MANIFEST,
+            line_of(&text, "description:"),
+            "Course description is empty".to_string(),
+        );
+    }
+    if manifest.tags.iter().any(|t| t.trim().is_empty()) {
+        out.warning(MANIFEST, line_of(&text, "tags:"), "Empty tag".to_string());
+    }
+    if manifest.steps.is_empty() {
+        out.error(
+            MANIFEST,
+            line_of(&text, "steps:"),
+            "Manifest has no steps".to_string(),
+        );
+    }
+
+    for chapter in &manifest.chapters {
+        let line = line_of(&text, &chapter.title);
+        if chapter.title.trim().is_empty() {
+            out.error(MANIFEST, line, "Chapter has no title".to_string());
+        }
+        if chapter.step_count == 0 {
+            out.warning(
+                MANIFEST,
+                line,
+                format!("Chapter {:?} has no steps", chapter.title),
+            );
+        }
+    }
+    if !manifest.steps.is_empty() && manifest.steps.iter().all(|s| s.optional) {
+        out.warning(
+            MANIFEST,
+            line_of(&text, "optional:"),
+            "Every step is optional, so the course can never show progress".to_string(),
+        );
+    }
+
+    let mut seen = HashSet::new();
+    let mut ids: HashMap<&str, &str> = HashMap::new();
+    for step in &manifest.steps {
+        let line = line_of(&text, &step.path);
+        if step.title.trim().is_empty() {
+            out.error(MANIFEST, line, format!("Step {} has no title", step.path));
+        }
+        if let Some(other) = ids.insert(&step.id, &step.path)
+            && other != step.path
+        {
+            out.error(
+                MANIFEST,
+                line_of(&text, &step.id).or(line),
+                format!(
+                    "Steps {other} and {} share id {:?}; progress would be shared",
+                    step.path, step.id
+                ),
+            );
+        }
+        if !seen.insert(step.path.as_str()) {
+            out.warning(
+                MANIFEST,
+                line,
+                format!("Step {} is listed twice", step.path),
+            );
+        }
+        if step.duration_minutes == Some(0) {
+            out.warning(
+                MANIFEST,
+                line,
+                format!("Step {} has a duration of 0 minutes", step.path),
+            );
+        }
+        if step.objectives.iter().any(|o| o.trim().is_empty()) {
+            out.warning(
+                MANIFEST,
+                line,
+                format!("Step {} has an empty objective", step.path),
+            );
+        }
+        let Some(relative) = safe_relative(&step.path).filter(|_| !step.path.is_empty()) else {
+            out.error(
+                MANIFEST,
+                line,
+                format!("Step path {:?} must be relative to the course", step.path),
+            );
+            continue;
+        };
+        let resolved = dir.join(relative);
+        match step.kind {
+            StepKind::Lesson => check_lesson(&mut out, &resolved, &step.path, line),
+            StepKind::Lab => check_lab(&mut out, &resolved, &step.path, line),
+        }
+    }
+
+    check_requires(&mut out, &text, &manifest);
+    check_locales(&mut out, &text, dir, &manifest);
+
+    for dependency in &manifest.dependencies {
+        let line = line_of(&text, &dependency.name);
+        if dependency.name.trim().is_empty() || dependency.check.trim().is_empty() {
+            out.error(
+                MANIFEST,
+                line,
+                "Dependency needs both a name and a check command".to_string(),
+            );
+        }
+        let mut unknown: Vec<&str> = dependency
+            .install
+            .keys()
+            .map(String::as_str)
+            .filter(|os| !KNOWN_OS.contains(os))
+            .collect();
+        unknown.sort_unstable();
+        for os in unknown {
+            out.warning(
+                MANIFEST,
+                line_of(&text, &format!("{os}:")).or(line),
+                format!(
+                    "Unknown OS {os:?} in install for {}; expected one of {}",
+                    dependency.name,
+                    KNOWN_OS.join(", ")
+                ),
+            );
+        }
+    }
+
+    out.0
+}
+
+/// Step `requires:` must name other steps of this course and must not loop,
+/// or the steps involved could never be completed.
+fn check_requires(out: &mut Diagnostics, text: &str, manifest: &Manifest) {
+    let mut edges: Vec<Vec<usize>> = vec![Vec::new(); manifest.steps.len()];
+    for (index, step) in manifest.steps.iter().enumerate() {
+        for reference in &step.requires {
+            match resolve_step(manifest, reference) {
+                Some(i) if i == index => out.error(
+                    MANIFEST,
+                    line_of(text, reference),
+                    format!("Step {} requires itself", step.path),
+                ),
+                Some(i) => edges[index].push(i),
+                None => out.error(
+                    MANIFEST,
+                    line_of(text, reference),
+                    format!("Step {} requires unknown step {reference:?}", step.path),
+                ),
+            }
+        }
+    }
+
+    // 0 = unvisited, 1 = on the current path, 2 = done.
+    fn visit(node: usize, edges: &[Vec<usize>], state: &mut [u8]) -> bool {
+        state[node] = 1;
+        for &next in &edges[node] {
+            if state[next] == 1 || (state[next] == 0 && visit(next, edges, state)) {
+                return true;
+            }
+        }
+        state[node] = 2;
+        false
+    }
+    let mut state = vec![0u8; edges.len()];
+    for start in 0..edges.len() {
+        if state[start] == 0 && visit(start, &edges, &mut state) {
+            let step = &manifest.steps[start];
+            out.error(
+                MANIFEST,
+                line_of(text, &step.path),
+                format!("Step {} is part of a requires cycle", step.path),
+            );
+            return;
+        }
+    }
+}
+
+/// Locale entries must point at real steps and chapters. A missing
+/// translated file isn't fatal — the base step is shown instead.
+fn check_locales(out: &mut Diagnostics, text: &str, dir: &Path, manifest: &Manifest) {
+    for (tag, locale) in &manifest.locales {
+        let tag_line = line_of(text, &format!("{tag}:"));
+        for title in locale.chapters.keys() {
+            if !manifest.chapters.iter().any(|c| &c.title == title) {
+                out.warning(
+                    MANIFEST,
+                    line_of(text, title).or(tag_line),
+                    format!("Locale {tag} translates unknown chapter {title:?}"),
+                );
+            }
+        }
+        let mut keys: Vec<&String> = locale.steps.keys().collect();
+        keys.sort_unstable();
+        for key in keys {
+            let line = line_of(text, key).or(tag_line);
+            if resolve_step(manifest, key).is_none() {
+                out.warning(
+                    MANIFEST,
+                    line,
+                    format!("Locale {tag} translates unknown step {key:?}"),
+                );
+                continue;
+            }
+            let Some(path) = &locale.steps[key].path else {
+                continue;
+            };
+            match safe_relative(path).filter(|_| !path.is_empty()) {
+                None => out.error(
+                    MANIFEST,
+                    line_of(text, path).or(line),
+                    format!("Locale {tag} step path {path:?} must be relative to the course"),
+                ),
+                Some(relative) if !dir.join(relative).exists() => out.warning(
+                    MANIFEST,
+                    line_of(text, path).or(line),
+                    format!("Locale {tag} step path {path} does not exist; the base step is shown"),
+                ),
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+/// A lesson is a Markdown file, or a directory of `.md` parts read in name order.
+fn check_lesson(out: &mut Diagnostics, resolved: &Path, step_path: &str, line: Option<usize>) {
+    if resolved.is_file() {
+        return;
+    }
+    if !resolved.is_dir() {
+        out.error(
+            MANIFEST,
+            line,
+            format!("Step path {step_path} does not exist"),
+        );
+        return;
+    }
+    let has_markdown = std::fs::read_dir(resolved).is_ok_and(|entries| {
+        entries
+            .filter_map(Result::ok)
+            .any(|e| e.path().extension().is_some_and(|ext| ext == "md"))
+    });
+    if !has_markdown {
+        out.error(
+            step_path,
+            None,
+            "Lesson directory has no .md files".to_string(),
+        );
+    }
+}
+
+fn check_lab(out: &mut Diagnostics, lab_dir: &Path, step_path: &str, line: Option<usize>) {
+    if !lab_dir.is_dir() {
+        out.error(
+            MANIFEST,
+            line,
+            format!("Lab directory {step_path} does not exist"),
+        );
+        return;
+    }
+
+    if !lab_dir.join("INSTRUCTIONS.md").is_file() {
+        out.error(
+            &format!("{step_path}/INSTRUCTIONS.md"),
+            None,
+            "Missing INSTRUCTIONS.md".to_string(),
+        );
+    }
+
+    let config_file = format!("{step_path}/lab.yaml");
+    if let Ok(raw) = std::fs::read_to_string(lab_dir.join("lab.yaml"))
+        && let Err(e) = serde_yml::from_str::<RawLabConfig>(&raw)
+    {
+        out.error(&config_file, e.location().map(|l| l.line()), e.to_string());
+    }
+
+    for (dir, role) in [
+        ("scaffold", "no starting files"),
+        ("solution", "no reference solution"),
+    ] {
+        if !lab_dir.join(dir).is_dir() {
+            out.warning(
+                &format!("{step_path}/{dir}"),
+                None,
+                format!("No {dir}/ directory; the lab has {role}"),
+            );
+        }
+    }
+}
+
+/// Runs the same checks import does against a course folder on disk, so
+/// authors see every problem at once instead of one runtime error at a time.
+#[tauri::command]
+pub async fn course_validate(path: String) -> Result<Vec<Diagnostic>, String> {
+    let path = Path::new(&path);
+    let dir = if path.is_file() {
+        path.parent().unwrap_or(path)
+    } else {
+        path
+    };
+    if !dir.is_dir() {
+        return Err(format!("Not a directory: {}", dir.display()));
+    }
+    Ok(validate_course(dir))
+}
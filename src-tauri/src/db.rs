@@ -1,5 +1,6 @@
 use parking_lot::Mutex;
-use rusqlite::Connection;
+use rusqlite::{Connection, params};
+use std::path::Path;
 
 /// Single SQLite connection behind a mutex.
 /// Desktop app with one user — no pool needed.
@@ -12,20 +13,131 @@ pub fn init() -> Result<Db, String> {
         std::fs::create_dir_all(parent)
             .map_err(|e| format!("Failed to create db directory: {e}"))?;
     }
+    Ok(Db(Mutex::new(open(&path)?)))
+}
 
-    let conn = Connection::open(&path).map_err(|e| format!("Failed to open database: {e}"))?;
+fn open(path: &Path) -> Result<Connection, String> {
+    let mut conn = Connection::open(path).map_err(|e| format!("Failed to open database: {e}"))?;
 
     conn.execute_batch("PRAGMA journal_mode = WAL;")
         .map_err(|e| format!("Failed to set WAL mode: {e}"))?;
+
+    // Foreign keys stay off while migrating: the pragma is a no-op inside a
+    // transaction, and rebuilding a table means dropping one that others reference.
+    migrate(&mut conn, path)?;
+
     conn.execute_batch("PRAGMA foreign_keys = ON;")
         .map_err(|e| format!("Failed to enable foreign keys: {e}"))?;
 
-    migrate(&conn)?;
+    Ok(conn)
+}
 
-    Ok(Db(Mutex::new(conn)))
+/// A schema change. Runs in its own transaction, with foreign keys off.
+struct Migration {
+    name: &'static str,
+    apply: fn(&Connection) -> Result<(), String>,
 }
 
-fn migrate(conn: &Connection) -> Result<(), String> {
+/// Every schema change in order. `PRAGMA user_version` records how many
+/// have been applied. Append new ones; never edit or reorder a shipped one.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        name: "baseline",
+        apply: baseline,
+    },
+    Migration {
+        name: "course revision",
+        apply: course_revision,
+    },
+    Migration {
+        name: "catalogs",
+        apply: catalogs,
+    },
+    Migration {
+        name: "step ids",
+        apply: step_ids,
+    },
+    Migration {
+        name: "manifest hash",
+        apply: manifest_hash,
+    },
+    Migration {
+        name: "study sessions",
+        apply: study_sessions,
+    },
+    Migration {
+        name: "notes",
+        apply: notes,
+    },
+];
+
+fn migrate(conn: &mut Connection, path: &Path) -> Result<(), String> {
+    let version: usize = conn
+        .pragma_query_value(None, "user_version", |row| row.get::<_, i64>(0))
+        .map_err(|e| format!("Failed to read schema version: {e}"))?
+        .try_into()
+        .map_err(|_| "Invalid schema version".to_string())?;
+    if version > MIGRATIONS.len() {
+        return Err(format!(
+            "Database schema v{version} is newer than this version of handhold supports (v{})",
+            MIGRATIONS.len()
+        ));
+    }
+    if version == MIGRATIONS.len() {
+        return Ok(());
+    }
+
+    let empty: bool = conn
+        .query_row("SELECT count(*) = 0 FROM sqlite_master", [], |row| {
+            row.get(0)
+        })
+        .map_err(|e| format!("Failed to read schema: {e}"))?;
+    if !empty {
+        backup(conn, path, version)?;
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let failed =
+            |e: String| format!("Migration {} ({}) failed: {e}", index + 1, migration.name);
+        let tx = conn.transaction().map_err(|e| failed(e.to_string()))?;
+        (migration.apply)(&tx).map_err(failed)?;
+        let violations: i64 = tx
+            .query_row("SELECT count(*) FROM pragma_foreign_key_check", [], |row| {
+                row.get(0)
+            })
+            .map_err(|e| failed(e.to_string()))?;
+        if violations > 0 {
+            return Err(failed(format!("{violations} foreign key violations")));
+        }
+        tx.pragma_update(None, "user_version", (index + 1) as i64)
+            .map_err(|e| failed(e.to_string()))?;
+        tx.commit().map_err(|e| failed(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Copies the database to `handhold.db.v{version}.bak` beside it before
+/// migrating, so a bad migration can be rolled back by hand.
+fn backup(conn: &Connection, path: &Path, version: usize) -> Result<(), String> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{version}.bak"));
+    let dest = path.with_file_name(name);
+    // VACUUM INTO refuses to overwrite. A leftover copy is of the same
+    // version, since a failed migration leaves the version unchanged.
+    if dest.exists() {
+        std::fs::remove_file(&dest)
+            .map_err(|e| format!("Failed to replace database backup: {e}"))?;
+    }
+    conn.execute("VACUUM INTO ?1", params![dest.to_string_lossy()])
+        .map_err(|e| format!("Failed to back up database: {e}"))?;
+    Ok(())
+}
+
+/// The schema of the last release before numbered migrations. Databases
+/// from that release or the one before it carry no version, so this still
+/// probes for the `github_url` rename and the search table, and creates a
+/// fresh database.
+fn baseline(conn: &Connection) -> Result<(), String> {
     conn.execute_batch(
         "
         CREATE TABLE IF NOT EXISTS course (
@@ -136,6 +248,8 @@ fn migrate(conn: &Connection) -> Result<(), String> {
                 INSERT INTO course_search(rowid, title, description)
                 VALUES (new.rowid, new.title, new.description);
             END;
+
+            INSERT INTO course_search(course_search) VALUES ('rebuild');
             ",
         )
         .map_err(|e| format!("FTS migration failed: {e}"))?;
@@ -143,3 +257,408 @@ fn migrate(conn: &Connection) -> Result<(), String> {
 
     Ok(())
 }
+
+/// Commit SHA the installed content came from. NULL means unknown: local
+/// and plain-HTTP courses, and anything imported before pinning.
+fn course_revision(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch("ALTER TABLE course ADD COLUMN revision TEXT;")
+        .map_err(|e| e.to_string())
+}
+
+/// Offline cache of the courses each configured catalog advertises.
+fn catalogs(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE catalog_entry (
+            source_url  TEXT PRIMARY KEY,
+            course_id   TEXT NOT NULL,
+            catalog     TEXT NOT NULL,
+            title       TEXT NOT NULL,
+            description TEXT NOT NULL,
+            revision    TEXT,
+            fetched_at  INTEGER NOT NULL,
+            CHECK (length(source_url) > 0)
+        ) STRICT;
+
+        CREATE TABLE catalog_tag (
+            source_url TEXT NOT NULL REFERENCES catalog_entry(source_url) ON DELETE CASCADE,
+            name       TEXT NOT NULL,
+            PRIMARY KEY (source_url, name),
+            CHECK (length(name) > 0)
+        ) STRICT, WITHOUT ROWID;
+        ",
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Keys progress by stable step id instead of position. The index-keyed
+/// rows move aside as legacy_*; `course::migrate_step_ids` maps them
+/// through each course's manifest, which SQL alone can't read.
+fn step_ids(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        ALTER TABLE step_completion RENAME TO legacy_step_completion;
+        ALTER TABLE step_position RENAME TO legacy_step_position;
+        ALTER TABLE slide_completion RENAME TO legacy_slide_completion;
+
+        CREATE TABLE course_step (
+            course_id  TEXT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+            step_index INTEGER NOT NULL,
+            step_id    TEXT NOT NULL,
+            optional   INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (course_id, step_index),
+            CHECK (step_index >= 0),
+            CHECK (length(step_id) > 0)
+        ) STRICT, WITHOUT ROWID;
+
+        CREATE TABLE step_completion (
+            course_id    TEXT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+            step_id      TEXT NOT NULL,
+            completed_at INTEGER NOT NULL,
+            PRIMARY KEY (course_id, step_id),
+            CHECK (length(step_id) > 0)
+        ) STRICT, WITHOUT ROWID;
+
+        CREATE TABLE step_position (
+            course_id   TEXT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+            step_id     TEXT NOT NULL,
+            slide_index INTEGER NOT NULL,
+            slide_count INTEGER,
+            PRIMARY KEY (course_id, step_id),
+            CHECK (slide_index >= 0),
+            CHECK (length(step_id) > 0)
+        ) STRICT, WITHOUT ROWID;
+
+        CREATE TABLE slide_completion (
+            course_id  TEXT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+            step_id    TEXT NOT NULL,
+            slide_id   TEXT NOT NULL,
+            PRIMARY KEY (course_id, step_id, slide_id),
+            CHECK (length(step_id) > 0),
+            CHECK (length(slide_id) > 0)
+        ) STRICT, WITHOUT ROWID;
+        ",
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Hash of the handhold.yaml `course_sync` last read. NULL until it has looked.
+fn manifest_hash(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch("ALTER TABLE course ADD COLUMN manifest_hash TEXT;")
+        .map_err(|e| e.to_string())
+}
+
+/// Time spent on each step: a session per visit, with heartbeats carrying
+/// the active time between them.
+fn study_sessions(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE study_session (
+            id         INTEGER PRIMARY KEY,
+            course_id  TEXT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+            step_id    TEXT NOT NULL,
+            step_kind  TEXT NOT NULL,
+            opened_at  INTEGER NOT NULL,
+            closed_at  INTEGER,
+            CHECK (length(step_id) > 0),
+            CHECK (step_kind IN ('lesson', 'lab'))
+        ) STRICT;
+
+        CREATE INDEX study_session_step ON study_session (course_id, step_id);
+
+        CREATE TABLE study_heartbeat (
+            session_id INTEGER NOT NULL REFERENCES study_session(id) ON DELETE CASCADE,
+            at         INTEGER NOT NULL,
+            active_ms  INTEGER NOT NULL,
+            CHECK (active_ms >= 0)
+        ) STRICT;
+
+        CREATE INDEX study_heartbeat_session ON study_heartbeat (session_id, at);
+        ",
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Learner-written notes, bookmarks and highlights, searchable like courses.
+/// A note hangs off a slide, a lab file's line range, or the step as a whole.
+fn notes(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE note (
+            id          INTEGER PRIMARY KEY,
+            course_id   TEXT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+            step_id     TEXT NOT NULL,
+            kind        TEXT NOT NULL,
+            slide_id    TEXT,
+            file_path   TEXT,
+            line_start  INTEGER,
+            line_end    INTEGER,
+            quote       TEXT,
+            body        TEXT NOT NULL,
+            created_at  INTEGER NOT NULL,
+            updated_at  INTEGER NOT NULL,
+            CHECK (length(step_id) > 0),
+            CHECK (kind IN ('note', 'bookmark', 'highlight')),
+            CHECK (slide_id IS NULL OR file_path IS NULL),
+            CHECK (line_start IS NULL OR file_path IS NOT NULL),
+            CHECK (line_start IS NULL OR line_start >= 1),
+            CHECK (line_end IS NULL OR line_end >= line_start)
+        ) STRICT;
+
+        CREATE INDEX note_step ON note (course_id, step_id);
+
+        CREATE VIRTUAL TABLE note_search USING fts5(
+            body, quote,
+            content='note',
+            content_rowid='id'
+        );
+
+        CREATE TRIGGER note_ins AFTER INSERT ON note BEGIN
+            INSERT INTO note_search(rowid, body, quote)
+            VALUES (new.id, new.body, new.quote);
+        END;
+
+        CREATE TRIGGER note_del AFTER DELETE ON note BEGIN
+            INSERT INTO note_search(note_search, rowid, body, quote)
+            VALUES ('delete', old.id, old.body, old.quote);
+        END;
+
+        CREATE TRIGGER note_upd AFTER UPDATE ON note BEGIN
+            INSERT INTO note_search(note_search, rowid, body, quote)
+            VALUES ('delete', old.id, old.body, old.quote);
+            INSERT INTO note_search(rowid, body, quote)
+            VALUES (new.id, new.body, new.quote);
+        END;
+        ",
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// The two releases before numbered migrations, in order. An install
+    /// that updated through both has both scripts applied.
+    const HISTORY: &[&str] = &[
+        // First release.
+        "CREATE TABLE course (
+            id          TEXT PRIMARY KEY,
+            github_url  TEXT NOT NULL UNIQUE,
+            local_path  TEXT NOT NULL,
+            title       TEXT NOT NULL,
+            description TEXT NOT NULL,
+            step_count  INTEGER NOT NULL,
+            added_at    INTEGER NOT NULL,
+            CHECK (length(id) > 0),
+            CHECK (length(github_url) > 0),
+            CHECK (step_count > 0)
+        ) STRICT;
+        CREATE TABLE tag (
+            course_id  TEXT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+            name       TEXT NOT NULL,
+            PRIMARY KEY (course_id, name),
+            CHECK (length(name) > 0)
+        ) STRICT, WITHOUT ROWID;
+        CREATE TABLE step_completion (
+            course_id    TEXT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+            step_index   INTEGER NOT NULL,
+            completed_at INTEGER NOT NULL,
+            PRIMARY KEY (course_id, step_index),
+            CHECK (step_index >= 0)
+        ) STRICT, WITHOUT ROWID;
+        CREATE TABLE step_position (
+            course_id   TEXT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+            step_index  INTEGER NOT NULL,
+            slide_index INTEGER NOT NULL,
+            slide_count INTEGER,
+            PRIMARY KEY (course_id, step_index),
+            CHECK (slide_index >= 0),
+            CHECK (step_index >= 0)
+        ) STRICT, WITHOUT ROWID;
+        CREATE TABLE app_route (
+            id    INTEGER PRIMARY KEY CHECK (id = 1),
+            kind  TEXT NOT NULL,
+            course_id  TEXT,
+            step_index INTEGER
+        ) STRICT;
+        INSERT INTO app_route (id, kind) VALUES (1, 'browser');
+        CREATE TABLE slide_completion (
+            course_id  TEXT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+            step_index INTEGER NOT NULL,
+            slide_id   TEXT NOT NULL,
+            PRIMARY KEY (course_id, step_index, slide_id),
+            CHECK (step_index >= 0),
+            CHECK (length(slide_id) > 0)
+        ) STRICT, WITHOUT ROWID;
+        CREATE TABLE lab_provision (
+            workspace_path TEXT PRIMARY KEY,
+            provisioned_at INTEGER NOT NULL
+        ) STRICT, WITHOUT ROWID;
+        INSERT INTO course VALUES ('c', 'https://github.com/a/b', '/courses/c', 'Rust basics', 'Learn Rust', 2, 1);
+        INSERT INTO tag VALUES ('c', 'rust');
+        INSERT INTO step_completion VALUES ('c', 0, 5);
+        INSERT INTO step_position VALUES ('c', 1, 3, 8);",
+        // Source URLs beyond GitHub, and search.
+        "ALTER TABLE course RENAME COLUMN github_url TO source_url;
+        CREATE VIRTUAL TABLE course_search USING fts5(
+            title, description, content='course', content_rowid='rowid'
+        );
+        INSERT INTO course_search(course_search) VALUES ('rebuild');
+        CREATE TRIGGER course_ins AFTER INSERT ON course BEGIN
+            INSERT INTO course_search(rowid, title, description)
+            VALUES (new.rowid, new.title, new.description);
+        END;
+        CREATE TRIGGER course_del AFTER DELETE ON course BEGIN
+            INSERT INTO course_search(course_search, rowid, title, description)
+            VALUES ('delete', old.rowid, old.title, old.description);
+        END;
+        CREATE TRIGGER course_upd AFTER UPDATE ON course BEGIN
+            INSERT INTO course_search(course_search, rowid, title, description)
+            VALUES ('delete', old.rowid, old.title, old.description);
+            INSERT INTO course_search(rowid, title, description)
+            VALUES (new.rowid, new.title, new.description);
+        END;",
+    ];
+
+    /// A fresh directory per test, since tests run in parallel.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("handhold-db-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn user_version(conn: &Connection) -> i64 {
+        conn.pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap()
+    }
+
+    /// Every table's columns, leaving out legacy_* tables, which
+    /// `course::migrate_step_ids` drops once their rows are moved.
+    fn columns(conn: &Connection) -> Vec<(String, String)> {
+        conn.prepare(
+            "SELECT m.name, p.name FROM sqlite_master m, pragma_table_info(m.name) p
+             WHERE m.type = 'table' AND m.name NOT LIKE 'legacy_%'
+             ORDER BY m.name, p.name",
+        )
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn fresh_database_is_current_without_backup() {
+        let dir = scratch_dir("fresh");
+        let conn = open(&dir.join("handhold.db")).unwrap();
+        assert_eq!(user_version(&conn), MIGRATIONS.len() as i64);
+        assert_eq!(count(&conn, "SELECT count(*) FROM app_route"), 1);
+        drop(conn);
+
+        let backups = std::fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".bak"))
+            .count();
+        assert_eq!(backups, 0);
+
+        // Reopening has nothing left to apply.
+        let conn = open(&dir.join("handhold.db")).unwrap();
+        assert_eq!(user_version(&conn), MIGRATIONS.len() as i64);
+    }
+
+    #[test]
+    fn upgrades_every_historical_schema() {
+        let fresh = open(&scratch_dir("reference").join("handhold.db")).unwrap();
+        let expected = columns(&fresh);
+
+        for release in 0..HISTORY.len() {
+            let dir = scratch_dir(&format!("release-{release}"));
+            let path = dir.join("handhold.db");
+            {
+                let conn = Connection::open(&path).unwrap();
+                for script in &HISTORY[..=release] {
+                    conn.execute_batch(script).unwrap();
+                }
+            }
+
+            let conn =
+                open(&path).unwrap_or_else(|e| panic!("release {release} failed to upgrade: {e}"));
+            assert_eq!(user_version(&conn), MIGRATIONS.len() as i64);
+            assert_eq!(columns(&conn), expected, "release {release}");
+
+            // Data survives, progress included, and search finds old courses.
+            assert_eq!(
+                count(
+                    &conn,
+                    "SELECT count(*) FROM course WHERE source_url = 'https://github.com/a/b'"
+                ),
+                1
+            );
+            assert_eq!(count(&conn, "SELECT count(*) FROM tag"), 1);
+            assert_eq!(
+                count(&conn, "SELECT count(*) FROM legacy_step_completion"),
+                1
+            );
+            assert_eq!(count(&conn, "SELECT count(*) FROM legacy_step_position"), 1);
+            assert_eq!(
+                count(
+                    &conn,
+                    "SELECT count(*) FROM course_search WHERE course_search MATCH 'rust'"
+                ),
+                1
+            );
+            assert_eq!(count(&conn, "PRAGMA foreign_keys"), 1);
+            drop(conn);
+
+            // The backup is the database as it was before migrating.
+            let backup = Connection::open(dir.join("handhold.db.v0.bak")).unwrap();
+            assert_eq!(user_version(&backup), 0);
+            assert_eq!(count(&backup, "SELECT count(*) FROM course"), 1);
+        }
+    }
+
+    #[test]
+    fn resumes_from_a_numbered_version() {
+        let dir = scratch_dir("resume");
+        let path = dir.join("handhold.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            for script in HISTORY {
+                conn.execute_batch(script).unwrap();
+            }
+            baseline(&conn).unwrap();
+            course_revision(&conn).unwrap();
+            conn.pragma_update(None, "user_version", 2).unwrap();
+        }
+
+        let conn = open(&path).unwrap();
+        assert_eq!(user_version(&conn), MIGRATIONS.len() as i64);
+        assert_eq!(
+            count(&conn, "SELECT count(*) FROM legacy_step_completion"),
+            1
+        );
+        assert_eq!(count(&conn, "SELECT count(*) FROM step_completion"), 0);
+        assert!(dir.join("handhold.db.v2.bak").exists());
+    }
+
+    #[test]
+    fn refuses_a_newer_schema() {
+        let dir = scratch_dir("newer");
+        let path = dir.join("handhold.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(HISTORY[0]).unwrap();
+            conn.pragma_update(None, "user_version", MIGRATIONS.len() as i64 + 1)
+                .unwrap();
+        }
+        assert!(open(&path).is_err());
+    }
+}
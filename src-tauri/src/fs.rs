@@ -99,7 +99,7 @@ pub async fn copy_scaffold(source_dir: String, target_dir: String) -> Result<(),
     copy_dir_recursive(src, dst)
 }
 
-fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), String> {
+pub(crate) fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), String> {
     for entry in
         fs::read_dir(src).map_err(|e| format!("Failed to read dir {}: {e}", src.display()))?
     {
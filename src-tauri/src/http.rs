@@ -0,0 +1,233 @@
+use parking_lot::Mutex;
+use reqwest::header::HeaderMap;
+use reqwest::{Certificate, NoProxy, Proxy, StatusCode};
+use std::time::Duration;
+
+use crate::settings::{MirrorRule, NetworkSettings};
+use crate::shell_env;
+
+// Shared HTTP clients for every network fetch. Proxy, extra CA roots and
+// timeouts come from `NetworkSettings` and the login-shell environment;
+// requests are rewritten through mirror rules and retried with backoff.
+
+// api.github.com rejects requests without a User-Agent.
+const USER_AGENT: &str = concat!("handhold/", env!("CARGO_PKG_VERSION"));
+
+/// First retry waits this long; each later one waits twice as long as the last.
+const BACKOFF_BASE: Duration = Duration::from_millis(500);
+
+/// Computes the Authorization header for the URL actually requested.
+pub type Authorize = fn(&str) -> Option<String>;
+
+// Rebuilt only when the settings change, so requests share a connection pool.
+static ASYNC_CLIENT: Mutex<Option<(NetworkSettings, reqwest::Client)>> = Mutex::new(None);
+static BLOCKING_CLIENT: Mutex<Option<(NetworkSettings, reqwest::blocking::Client)>> =
+    Mutex::new(None);
+
+/// An error with its causes, so "error sending request" also says why
+/// (unknown issuer, connection refused, proxy rejected the tunnel).
+pub fn describe(error: &dyn std::error::Error) -> String {
+    let mut out = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let text = cause.to_string();
+        if !out.contains(&text) {
+            out.push_str(": ");
+            out.push_str(&text);
+        }
+        source = cause.source();
+    }
+    out
+}
+
+/// `url` with the first matching mirror prefix replaced.
+pub fn rewrite(url: &str, mirrors: &[MirrorRule]) -> String {
+    mirrors
+        .iter()
+        .filter(|rule| !rule.from.is_empty())
+        .find_map(|rule| {
+            url.strip_prefix(&rule.from)
+                .map(|rest| format!("{}{rest}", rule.to))
+        })
+        .unwrap_or_else(|| url.to_string())
+}
+
+fn env_any(names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| shell_env::var(name))
+}
+
+fn proxies(network: &NetworkSettings) -> Result<Vec<Proxy>, String> {
+    let no_proxy = match &network.no_proxy {
+        Some(list) => NoProxy::from_string(list),
+        None => env_any(&["NO_PROXY", "no_proxy"]).and_then(|list| NoProxy::from_string(&list)),
+    };
+    let invalid = |e: reqwest::Error| format!("Invalid proxy URL: {}", describe(&e));
+
+    if let Some(url) = network.proxy.as_ref().filter(|p| !p.trim().is_empty()) {
+        return Ok(vec![Proxy::all(url).map_err(invalid)?.no_proxy(no_proxy)]);
+    }
+    let mut proxies = Vec::new();
+    if let Some(url) = env_any(&["HTTPS_PROXY", "https_proxy"]) {
+        proxies.push(
+            Proxy::https(&url)
+                .map_err(invalid)?
+                .no_proxy(no_proxy.clone()),
+        );
+    }
+    if let Some(url) = env_any(&["HTTP_PROXY", "http_proxy"]) {
+        proxies.push(
+            Proxy::http(&url)
+                .map_err(invalid)?
+                .no_proxy(no_proxy.clone()),
+        );
+    }
+    if let Some(url) = env_any(&["ALL_PROXY", "all_proxy"]) {
+        proxies.push(Proxy::all(&url).map_err(invalid)?.no_proxy(no_proxy));
+    }
+    Ok(proxies)
+}
+
+fn root_certificates(network: &NetworkSettings) -> Result<Vec<Certificate>, String> {
+    let mut certificates = Vec::new();
+    let bundles = network
+        .ca_certificates
+        .iter()
+        .cloned()
+        .chain(shell_env::var("SSL_CERT_FILE"));
+    for path in bundles {
+        let pem = std::fs::read(&path)
+            .map_err(|e| format!("Failed to read CA certificates {path}: {e}"))?;
+        let parsed = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("Invalid CA certificates in {path}: {}", describe(&e)))?;
+        certificates.extend(parsed);
+    }
+    Ok(certificates)
+}
+
+fn build_client(network: &NetworkSettings) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(Duration::from_secs(network.connect_timeout_secs))
+        .read_timeout(Duration::from_secs(network.read_timeout_secs));
+    for proxy in proxies(network)? {
+        builder = builder.proxy(proxy);
+    }
+    for certificate in root_certificates(network)? {
+        builder = builder.add_root_certificate(certificate);
+    }
+    builder
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", describe(&e)))
+}
+
+fn build_blocking_client(network: &NetworkSettings) -> Result<reqwest::blocking::Client, String> {
+    // The blocking client's timeout bounds each read, not the whole body.
+    let mut builder = reqwest::blocking::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(Duration::from_secs(network.connect_timeout_secs))
+        .timeout(Duration::from_secs(network.read_timeout_secs));
+    for proxy in proxies(network)? {
+        builder = builder.proxy(proxy);
+    }
+    for certificate in root_certificates(network)? {
+        builder = builder.add_root_certificate(certificate);
+    }
+    builder
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", describe(&e)))
+}
+
+fn client(network: &NetworkSettings) -> Result<reqwest::Client, String> {
+    let mut cached = ASYNC_CLIENT.lock();
+    if let Some((settings, client)) = cached.as_ref()
+        && settings == network
+    {
+        return Ok(client.clone());
+    }
+    let client = build_client(network)?;
+    *cached = Some((network.clone(), client.clone()));
+    Ok(client)
+}
+
+fn blocking_client(network: &NetworkSettings) -> Result<reqwest::blocking::Client, String> {
+    let mut cached = BLOCKING_CLIENT.lock();
+    if let Some((settings, client)) = cached.as_ref()
+        && settings == network
+    {
+        return Ok(client.clone());
+    }
+    let client = build_blocking_client(network)?;
+    *cached = Some((network.clone(), client.clone()));
+    Ok(client)
+}
+
+fn retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn retryable_error(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout()
+}
+
+fn backoff(attempt: u32) -> Duration {
+    BACKOFF_BASE * 2u32.saturating_pow(attempt)
+}
+
+/// Async GET through the shared client. The last response is returned even
+/// when retries run out on a 429 or 5xx, so callers still see the status.
+pub async fn get(url: &str, authorize: Authorize) -> Result<reqwest::Response, String> {
+    let network = crate::settings::current().network;
+    let url = rewrite(url, &network.mirrors);
+    let client = client(&network)?;
+    let mut attempt = 0;
+    loop {
+        let mut request = client.get(&url);
+        if let Some(value) = authorize(&url) {
+            request = request.header(reqwest::header::AUTHORIZATION, value);
+        }
+        let retry = attempt < network.retries;
+        match request.send().await {
+            Ok(resp) if retry && retryable_status(resp.status()) => {}
+            Ok(resp) => return Ok(resp),
+            Err(e) if retry && retryable_error(&e) => {}
+            Err(e) => return Err(describe(&e)),
+        }
+        tokio::time::sleep(backoff(attempt)).await;
+        attempt += 1;
+    }
+}
+
+/// Blocking counterpart of `get`, for downloads on worker threads.
+pub fn get_blocking(
+    url: &str,
+    authorize: Authorize,
+) -> Result<reqwest::blocking::Response, String> {
+    get_blocking_with(url, authorize, &HeaderMap::new())
+}
+
+/// `get_blocking` with extra request headers, e.g. `Range` or `If-None-Match`.
+pub fn get_blocking_with(
+    url: &str,
+    authorize: Authorize,
+    headers: &HeaderMap,
+) -> Result<reqwest::blocking::Response, String> {
+    let network = crate::settings::current().network;
+    let url = rewrite(url, &network.mirrors);
+    let client = blocking_client(&network)?;
+    let mut attempt = 0;
+    loop {
+        let mut request = client.get(&url).headers(headers.clone());
+        if let Some(value) = authorize(&url) {
+            request = request.header(reqwest::header::AUTHORIZATION, value);
+        }
+        let retry = attempt < network.retries;
+        match request.send() {
+            Ok(resp) if retry && retryable_status(resp.status()) => {}
+            Ok(resp) => return Ok(resp),
+            Err(e) if retry && retryable_error(&e) => {}
+            Err(e) => return Err(describe(&e)),
+        }
+        std::thread::sleep(backoff(attempt));
+        attempt += 1;
+    }
+}
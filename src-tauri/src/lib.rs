@@ -3,6 +3,7 @@ mod course;
 mod db;
 mod fs;
 mod git;
+mod http;
 mod lsp;
 mod paths;
 mod preview;
@@ -80,12 +81,44 @@ fn build_menu(app: &tauri::AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
     Menu::with_items(app, &[&app_menu, &edit_menu, &view_menu, &window_menu])
 }
 
+/// Hands `handhold://` links to the course router: links that launched the
+/// app, links opened while it runs, and links forwarded by a second launch.
+fn init_deep_links(app: &tauri::AppHandle) {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    // Installers register the scheme; this covers dev builds and AppImages.
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        eprintln!("[deep-link] failed to register scheme: {e}");
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        course::open_deep_links(&handle, &event.urls());
+    });
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        course::open_deep_links(app, &urls);
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let database = db::init().expect("Failed to initialize database");
+    if let Err(e) = course::migrate_step_ids(&database) {
+        eprintln!("[migrate] {e}");
+    }
     let active_composes = container::ActiveComposes::new();
+    let active_imports = course::ActiveImports::new();
+    let deep_links = course::DeepLinks::new();
 
     let app = tauri::Builder::default()
+        // Registered first: a second launch passes its link here and exits.
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.set_focus();
+            }
+        }))
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
@@ -93,10 +126,13 @@ pub fn run() {
         .setup(|app| {
             let menu = build_menu(app.handle())?;
             app.set_menu(menu)?;
+            init_deep_links(app.handle());
             Ok(())
         })
         .manage(database)
         .manage(active_composes)
+        .manage(active_imports)
+        .manage(deep_links)
         .invoke_handler(tauri::generate_handler![
             tts::synthesize,
             tts::export_audio,
@@ -148,15 +184,46 @@ pub fn run() {
             container::container_action,
             // Course browser
             course::course_import,
+            course::course_import_cancel,
+            course::course_import_local,
+            course::course_validate,
+            course::course_import_archive,
+            course::course_export,
+            course::catalog_refresh,
+            course::catalog_list,
+            course::catalog_search,
+            course::catalog_install,
+            course::credential_list,
+            course::credential_set,
+            course::credential_remove,
+            course::publisher_list,
+            course::publisher_trust,
+            course::publisher_untrust,
             course::course_list,
             course::course_search,
             course::course_tags,
             course::course_by_tag,
             course::course_delete,
+            course::course_check_update,
+            course::course_update,
             course::step_complete,
             course::step_progress,
+            course::course_unlocks,
             course::route_save,
             course::route_load,
+            course::progress_export,
+            course::progress_import,
+            course::study_session_open,
+            course::study_heartbeat,
+            course::study_session_close,
+            course::progress_stats,
+            course::note_create,
+            course::note_update,
+            course::note_delete,
+            course::note_list,
+            course::note_search,
+            course::note_export,
+            course::deep_link_subscribe,
             course::course_get,
             course::course_manifest,
             course::course_read_step,
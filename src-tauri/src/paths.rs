@@ -26,6 +26,11 @@ pub fn workspaces_dir() -> PathBuf {
     handhold_dir().join("workspaces")
 }
 
+/// Forge tarballs kept between imports, keyed by URL and revision.
+pub fn download_cache_dir() -> PathBuf {
+    handhold_dir().join("cache").join("downloads")
+}
+
 pub fn db_path() -> PathBuf {
     handhold_dir().join("handhold.db")
 }
@@ -33,3 +38,11 @@ pub fn db_path() -> PathBuf {
 pub fn settings_path() -> PathBuf {
     handhold_dir().join("settings.json")
 }
+
+pub fn trusted_publishers_path() -> PathBuf {
+    handhold_dir().join("trusted-publishers.json")
+}
+
+pub fn credentials_path() -> PathBuf {
+    handhold_dir().join("credentials.json")
+}
@@ -15,6 +15,73 @@ pub struct AppSettings {
     pub sidebar_collapsed: bool,
     #[serde(default)]
     pub suppress_close_confirm: bool,
+    /// Self-hosted forges the course importer should recognise by hostname.
+    #[serde(default)]
+    pub forges: Vec<ForgeHost>,
+    /// Course catalog indexes: `http(s)://` URLs or local file paths.
+    #[serde(default)]
+    pub catalogs: Vec<String>,
+    /// BCP 47 tag such as `es` or `ja-JP`. Courses without it show their base text.
+    #[serde(default)]
+    pub preferred_locale: Option<String>,
+    #[serde(default)]
+    pub network: NetworkSettings,
+}
+
+/// Maps a custom hostname onto the forge API it speaks.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ForgeHost {
+    pub host: String,
+    pub kind: ForgeKind,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    Gitlab,
+    /// Forgejo is a Gitea fork with the same URL layout.
+    #[serde(alias = "forgejo")]
+    Gitea,
+    /// bitbucket.org only. Bitbucket Server and Data Center use different
+    /// routes and API, so course URLs on other hosts don't parse.
+    Bitbucket,
+}
+
+/// Outbound HTTP for course imports, updates and catalogs. Unset fields
+/// fall back to the environment: `HTTPS_PROXY`, `NO_PROXY`, `SSL_CERT_FILE`.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkSettings {
+    /// Proxy URL for every request; overrides the `*_PROXY` variables.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Comma-separated hosts that skip the proxy; overrides `NO_PROXY`.
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+    /// PEM files trusted on top of the bundled roots, e.g. an intercepting proxy's CA.
+    #[serde(default)]
+    pub ca_certificates: Vec<String>,
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// Longest wait for the next bytes of a response, not the whole download.
+    #[serde(default = "default_read_timeout_secs")]
+    pub read_timeout_secs: u64,
+    /// Extra attempts after a connection failure, timeout, 429 or 5xx.
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// URL prefix rewrites applied before any request, first match wins.
+    #[serde(default)]
+    pub mirrors: Vec<MirrorRule>,
+}
+
+/// Sends requests for URLs starting with `from` to `to` plus the rest of
+/// the URL, e.g. `https://github.com/` → `https://artifacts.corp/github/`.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MirrorRule {
+    pub from: String,
+    pub to: String,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -64,6 +131,32 @@ fn default_sidebar_panel() -> String {
     "explorer".to_string()
 }
 
+fn default_connect_timeout_secs() -> u64 {
+    15
+}
+
+fn default_read_timeout_secs() -> u64 {
+    60
+}
+
+fn default_retries() -> u32 {
+    2
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            no_proxy: None,
+            ca_certificates: Vec::new(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+            read_timeout_secs: default_read_timeout_secs(),
+            retries: default_retries(),
+            mirrors: Vec::new(),
+        }
+    }
+}
+
 impl Default for EditorSettings {
     fn default() -> Self {
         Self {
@@ -87,12 +180,15 @@ impl Default for AppSettings {
             sidebar_panel: default_sidebar_panel(),
             sidebar_collapsed: false,
             suppress_close_confirm: false,
+            forges: Vec::new(),
+            catalogs: Vec::new(),
+            preferred_locale: None,
+            network: NetworkSettings::default(),
         }
     }
 }
 
-#[tauri::command]
-pub async fn load_settings() -> Result<AppSettings, String> {
+fn read_settings() -> Result<AppSettings, String> {
     let path = crate::paths::settings_path();
     if !path.exists() {
         return Ok(AppSettings::default());
@@ -102,6 +198,17 @@ pub async fn load_settings() -> Result<AppSettings, String> {
     serde_json::from_str(&content).map_err(|e| format!("Failed to parse settings: {e}"))
 }
 
+/// Settings for backend consumers. A broken file falls back to defaults
+/// instead of failing the caller — the frontend surfaces parse errors.
+pub fn current() -> AppSettings {
+    read_settings().unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn load_settings() -> Result<AppSettings, String> {
+    read_settings()
+}
+
 #[tauri::command]
 pub async fn save_settings(settings: AppSettings) -> Result<(), String> {
     let path = crate::paths::settings_path();
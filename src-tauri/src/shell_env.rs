@@ -15,10 +15,36 @@ struct ShellEnv {
 
 static RESOLVED: OnceLock<ShellEnv> = OnceLock::new();
 
+/// Login-shell variables forwarded to child processes and readable via `var`.
+/// The proxy and CA variables are what corporate networks set in rc files.
+const FORWARDED_VARS: &[&str] = &[
+    "LANG",
+    "USER",
+    "LOGNAME",
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+    "NO_PROXY",
+    "no_proxy",
+    "SSL_CERT_FILE",
+];
+
 fn resolve() -> &'static ShellEnv {
     RESOLVED.get_or_init(query_login_shell_env)
 }
 
+/// A variable from the process environment, or from the login shell when a
+/// GUI launch left it out. Empty values count as unset.
+pub fn var(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .or_else(|| resolve().vars.get(name).cloned())
+}
+
 /// Injects the resolved PATH (and HOME, LANG, USER) into a
 /// `std::process::Command` so it can find tools installed by Homebrew,
 /// nvm, pyenv, cargo, etc.
@@ -58,10 +84,13 @@ fn query_login_shell_env() -> ShellEnv {
     // -l = login shell (sources /etc/zprofile, ~/.zprofile, ~/.zshrc, etc.)
     // -i is NOT used — it triggers prompt-related side effects.
     // printf is more portable than echo for \n across sh/bash/zsh.
-    let script = r#"printf '__PATH=%s\n__HOME=%s\n__LANG=%s\n__USER=%s\n__LOGNAME=%s\n' "$PATH" "$HOME" "$LANG" "$USER" "$LOGNAME""#;
+    let mut script = r#"printf '__PATH=%s\n__HOME=%s\n' "$PATH" "$HOME""#.to_string();
+    for name in FORWARDED_VARS {
+        script.push_str(&format!(r#"; printf '__{name}=%s\n' "${name}""#));
+    }
 
     let output = Command::new(&shell)
-        .args(["-l", "-c", script])
+        .args(["-l", "-c", &script])
         .stdin(std::process::Stdio::null())
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::null())
@@ -78,18 +107,13 @@ fn query_login_shell_env() -> ShellEnv {
                 path = val.to_string();
             } else if let Some(val) = line.strip_prefix("__HOME=") {
                 home = val.to_string();
-            } else if let Some(val) = line.strip_prefix("__LANG=")
-                && !val.is_empty()
-            {
-                vars.insert("LANG".to_string(), val.to_string());
-            } else if let Some(val) = line.strip_prefix("__USER=")
-                && !val.is_empty()
-            {
-                vars.insert("USER".to_string(), val.to_string());
-            } else if let Some(val) = line.strip_prefix("__LOGNAME=")
+            } else if let Some((name, val)) = line
+                .strip_prefix("__")
+                .and_then(|rest| rest.split_once('='))
+                && FORWARDED_VARS.contains(&name)
                 && !val.is_empty()
             {
-                vars.insert("LOGNAME".to_string(), val.to_string());
+                vars.insert(name.to_string(), val.to_string());
             }
         }
     }
@@ -2,18 +2,18 @@ import { useCallback, useEffect, useMemo, useRef, useState } from "react";
 import { QueryClient, QueryClientProvider, useQuery, useQueryClient } from "@tanstack/react-query";
 import { HotkeysProvider } from "@tanstack/react-hotkeys";
 import { invoke } from "@tauri-apps/api/core";
-import { onOpenUrl } from "@tauri-apps/plugin-deep-link";
 import { Presentation } from "@/presentation/Presentation";
 import { parseLesson } from "@/parser/parse-lesson";
 import { initSettings, useSettingsStore } from "@/lab/settings-store";
 import { Browser } from "@/browser/Browser";
-import { watchDir, coursesDirPath, courseSync } from "@/browser/tauri";
+import { watchDir, coursesDirPath, courseSync, deepLinkSubscribe } from "@/browser/tauri";
 import { useRoute } from "@/browser/use-route";
 import {
   useCourse,
   useCourseManifest,
   useCourseStep,
   useCompleteStep,
+  useCourseUnlocks,
   useStepProgress,
   useSlidePosition,
   useSaveSlidePosition,
@@ -26,6 +26,7 @@ import { parseLab } from "@/lab/parse-lab";
 import { Lab } from "@/lab/Lab";
 import { CourseNavBar } from "@/course/CourseNavBar";
 import { DependencyModal } from "@/course/DependencyModal";
+import { useStudySession } from "@/course/use-study-session";
 import { useGlobalTtsPrefetch } from "@/tts/use-prefetch-tts";
 import { UpdateBanner } from "@/updater/UpdateBanner";
 import type { CourseRecord, ManifestStep } from "@/types/browser";
@@ -49,10 +50,18 @@ function useCoursesDirWatcher() {
 
     const sync = async () => {
       const result = await courseSync();
-      if (result.added > 0 || result.removed > 0) {
+      if (result.added > 0 || result.removed > 0 || result.updated.length > 0) {
         qc.invalidateQueries({ queryKey: ["courses"] });
         qc.invalidateQueries({ queryKey: ["course-tags"] });
       }
+      for (const { id } of result.updated) {
+        qc.invalidateQueries({ queryKey: ["course", id] });
+        qc.invalidateQueries({ queryKey: ["course-manifest", id] });
+        qc.invalidateQueries({ queryKey: ["course-unlocks", id] });
+      }
+      for (const { path, reason } of result.invalid) {
+        console.warn(`[courses-watcher] ${path}: ${reason}`);
+      }
     };
 
     const start = async () => {
@@ -84,30 +93,26 @@ function AppContent() {
   useCoursesDirWatcher();
   useGlobalTtsPrefetch(route.kind === "course" ? route.courseId : null);
 
+  // The backend parses and validates handhold:// links and saves the route;
+  // this follows it. Import links only prefill the dialog — a web page must
+  // not be able to install a course without the user confirming.
   useEffect(() => {
-    const promise = onOpenUrl((urls) => {
-      for (const raw of urls) {
-        let parsed: URL;
-        try { parsed = new URL(raw); } catch { continue; }
-
-        if (parsed.hostname === "import") {
-          const url = parsed.searchParams.get("url");
-          if (url) {
-            navigate({ kind: "browser" });
-            setPendingImportUrl(url);
-          }
-        } else if (parsed.hostname === "open") {
-          const parts = parsed.pathname.split("/").filter(Boolean);
-          const courseId = parts[0];
-          if (!courseId) continue;
-          const stepIndex = parts[1] ? parseInt(parts[1], 10) : 0;
-          if (!Number.isNaN(stepIndex)) {
-            navigate({ kind: "course", courseId, stepIndex });
-          }
-        }
+    deepLinkSubscribe((event) => {
+      switch (event.kind) {
+        case "navigate":
+          navigate(event.route);
+          break;
+        case "importRequested":
+          navigate({ kind: "browser" });
+          setPendingImportUrl(event.sourceUrl);
+          break;
+        case "rejected":
+          console.warn(`[deep-link] ${event.url}: ${event.reason}`);
+          break;
       }
+    }).catch((err) => {
+      console.error("[deep-link] failed to subscribe:", err);
     });
-    return () => { promise.then((fn) => fn()); };
   }, [navigate]);
 
   const { isLoading } = useQuery({
@@ -144,6 +149,10 @@ function AppContent() {
           <CourseShell
             key={route.courseId}
             courseId={route.courseId}
+            stepIndex={route.stepIndex}
+            onStepChange={(stepIndex) =>
+              navigate({ kind: "course", courseId: route.courseId, stepIndex })
+            }
             onBack={() => navigate({ kind: "browser" })}
           />
         )}
@@ -154,14 +163,18 @@ function AppContent() {
 
 type CourseShellProps = {
   readonly courseId: string;
+  /** Step the route points at. Deep links can change it while the course is open. */
+  readonly stepIndex: number;
+  readonly onStepChange: (stepIndex: number) => void;
   readonly onBack: () => void;
 };
 
-function CourseShell({ courseId, onBack }: CourseShellProps) {
+function CourseShell({ courseId, stepIndex: routeStepIndex, onStepChange, onBack }: CourseShellProps) {
   const { data: manifest, isLoading } = useCourseManifest(courseId);
   const { data: course } = useCourse(courseId);
   const { data: progressIndices } = useStepProgress(courseId);
-  const [rawStepIndex, setStepIndex] = useState(0);
+  const { data: unlocks } = useCourseUnlocks(courseId);
+  const [rawStepIndex, setStepIndex] = useState(routeStepIndex);
   const completeStep = useCompleteStep();
   const stepIndexRef = useRef(rawStepIndex);
   const { data: homeDir = "/" } = useHomeDirPath();
@@ -172,6 +185,31 @@ function CourseShell({ courseId, onBack }: CourseShellProps) {
     [progressIndices],
   );
 
+  // Advisory only when the author allows free navigation.
+  const lockedSteps = useMemo(() => {
+    if (!unlocks || unlocks.freeNavigation) return new Set<number>();
+    if (unlocks.course.length > 0) {
+      return new Set(manifest?.steps.map((_, i) => i) ?? []);
+    }
+    return new Set(unlocks.steps.map((lock) => lock.stepIndex));
+  }, [unlocks, manifest]);
+
+  // Follow the route when it changes from outside, e.g. a handhold:// link.
+  useEffect(() => {
+    setStepIndex(routeStepIndex);
+  }, [routeStepIndex]);
+
+  // Keep the route on the current step, so a link to the step the route
+  // last held still registers as a change, and reopening resumes here.
+  useEffect(() => {
+    if (manifest && rawStepIndex !== routeStepIndex) onStepChange(rawStepIndex);
+  }, [rawStepIndex]); // eslint-disable-line react-hooks/exhaustive-deps
+
+  useStudySession(
+    courseId,
+    manifest ? Math.min(Math.max(0, rawStepIndex), manifest.steps.length - 1) : null,
+  );
+
   if (isLoading || !manifest) return null;
 
   const total = manifest.steps.length;
@@ -212,8 +250,10 @@ function CourseShell({ courseId, onBack }: CourseShellProps) {
           prev: () => setStepIndex((i) => (i > 0 ? i - 1 : 0)),
           stepTitle: currentStep.title,
           steps: manifest.steps,
+          chapters: manifest.chapters,
           goTo: setStepIndex,
           completedSteps,
+          lockedSteps,
         }}
         onBack={onBack}
       />
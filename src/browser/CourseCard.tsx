@@ -33,8 +33,8 @@ export function CourseCard({ course, onOpen }: CourseCardProps) {
   const deleteCourse = useDeleteCourse();
 
   const progressPercent =
-    course.stepCount > 0
-      ? Math.round((course.completedSteps / course.stepCount) * 100)
+    course.requiredSteps > 0
+      ? Math.round((course.completedSteps / course.requiredSteps) * 100)
       : 0;
 
   function handleDelete() {
@@ -73,7 +73,7 @@ export function CourseCard({ course, onOpen }: CourseCardProps) {
             <div className="space-y-1.5">
               <div className="flex items-center justify-between text-[10px] text-muted-foreground tabular-nums">
                 <span>
-                  {course.completedSteps} / {course.stepCount} steps
+                  {course.completedSteps} / {course.requiredSteps} steps
                 </span>
                 <span>{progressPercent}%</span>
               </div>
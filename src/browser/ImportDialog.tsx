@@ -1,6 +1,7 @@
 import { useEffect, useRef, useState } from "react";
 import { useImportCourse } from "@/browser/use-courses";
-import type { ImportResult } from "@/types/browser";
+import { courseImportCancel } from "@/browser/tauri";
+import type { Diagnostic, ImportEvent, ImportResult } from "@/types/browser";
 import {
   Dialog,
   DialogContent,
@@ -28,29 +29,69 @@ const IMPORT_MESSAGES: Record<
   badManifest: "The handhold.yaml manifest is invalid.",
   alreadyExists: "This course is already in your library.",
   downloadFailed: "Failed to download the course.",
+  badPackage: "That file isn't a valid course package.",
+  integrityFailed: "Course content failed its integrity check",
+  untrustedPublisher: "The course is signed by a publisher you haven't trusted",
+  cancelled: "Import cancelled.",
+  unauthorized: "Access denied. Add a token for this host and try again",
+  invalid: "The course has problems",
+  needsNewerApp: "This course needs a newer version of Handhold. Update the app to import it.",
+  networkError: "Couldn't reach the server. Check your connection or proxy settings",
+  unpinned: "Couldn't resolve the course to a commit. Try again in a few minutes",
 };
 
+/** First error, with a count of the rest. */
+function formatDiagnostic(diagnostics: readonly Diagnostic[]): string {
+  const errors = diagnostics.filter((d) => d.severity === "error");
+  const [first] = errors;
+  if (!first) return "";
+  const location = first.line !== null ? `${first.file}:${first.line}` : first.file;
+  const more = errors.length > 1 ? ` (+${errors.length - 1} more)` : "";
+  return `${location} ${first.message}${more}`;
+}
+
+function formatMegabytes(bytes: number): string {
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
+
+function describeProgress(event: ImportEvent): string {
+  switch (event.event) {
+    case "resolving":
+      return "Resolving course...";
+    case "manifestFetched":
+      return `Found "${event.title}" (${event.stepCount} steps)`;
+    case "downloaded":
+      return event.total !== null
+        ? `Downloaded ${formatMegabytes(event.bytes)} of ${formatMegabytes(event.total)}`
+        : `Downloaded ${formatMegabytes(event.bytes)}`;
+    case "extracted":
+      return `Extracted ${event.files} files`;
+    case "registering":
+      return "Adding to library...";
+  }
+}
+
 export function ImportDialog({ open, onOpenChange, initialUrl }: ImportDialogProps) {
   const [url, setUrl] = useState(initialUrl ?? "");
   const [error, setError] = useState("");
-  const importMutation = useImportCourse();
-  const autoSubmitted = useRef(false);
+  const [progress, setProgress] = useState("");
+  const importMutation = useImportCourse((event) => setProgress(describeProgress(event)));
+  const pendingUrl = useRef("");
 
+  // Prefill only. Links from outside the app can name any course, and
+  // courses run setup commands, so importing waits for the user.
   useEffect(() => {
     if (initialUrl && initialUrl.length > 0) {
       setUrl(initialUrl);
     }
   }, [initialUrl]);
 
-  useEffect(() => {
-    if (!open || !initialUrl || initialUrl.length === 0 || autoSubmitted.current) return;
-    autoSubmitted.current = true;
-    doImport(initialUrl);
-  }, [open, initialUrl]); // eslint-disable-line react-hooks/exhaustive-deps
-
   function doImport(value: string) {
     setError("");
+    setProgress("");
+    pendingUrl.current = value.trim();
     importMutation.mutate(value.trim(), {
+      onSettled: () => setProgress(""),
       onSuccess: (result) => {
         if (result.kind === "ok") {
           setUrl("");
@@ -58,7 +99,13 @@ export function ImportDialog({ open, onOpenChange, initialUrl }: ImportDialogPro
           return;
         }
         const base = IMPORT_MESSAGES[result.kind];
-        const detail = "reason" in result ? `: ${result.reason}` : "";
+        const detail =
+          "reason" in result ? `: ${result.reason}`
+          : "file" in result ? `: ${result.file}`
+          : "publisher" in result ? `: ${result.publisher}`
+          : "host" in result ? `: ${result.host}`
+          : "diagnostics" in result ? `: ${formatDiagnostic(result.diagnostics)}`
+          : "";
         setError(`${base}${detail}`);
       },
       onError: (err) => {
@@ -67,6 +114,14 @@ export function ImportDialog({ open, onOpenChange, initialUrl }: ImportDialogPro
     });
   }
 
+  function handleCancel() {
+    if (importMutation.isPending) {
+      void courseImportCancel(pendingUrl.current);
+      return;
+    }
+    onOpenChange(false);
+  }
+
   function handleSubmit(e: React.FormEvent<HTMLFormElement>) {
     e.preventDefault();
     doImport(url);
@@ -78,7 +133,9 @@ export function ImportDialog({ open, onOpenChange, initialUrl }: ImportDialogPro
         <DialogHeader>
           <DialogTitle>Add Course</DialogTitle>
           <DialogDescription>
-            Paste a link to a handhold.yaml file, or a GitHub repository URL.
+            {initialUrl
+              ? "A link asked to add this course. Check the URL and import only if you trust its source."
+              : "Paste a link to a handhold.yaml file, or a GitHub repository URL."}
           </DialogDescription>
         </DialogHeader>
 
@@ -94,6 +151,12 @@ export function ImportDialog({ open, onOpenChange, initialUrl }: ImportDialogPro
             aria-invalid={error.length > 0 ? true : undefined}
           />
 
+          {importMutation.isPending && progress.length > 0 && (
+            <p className="text-xs text-muted-foreground" aria-live="polite">
+              {progress}
+            </p>
+          )}
+
           {error.length > 0 && (
             <p className="text-xs text-destructive" role="alert">
               {error}
@@ -104,7 +167,7 @@ export function ImportDialog({ open, onOpenChange, initialUrl }: ImportDialogPro
             <Button
               type="button"
               variant="outline"
-              onClick={() => onOpenChange(false)}
+              onClick={handleCancel}
             >
               Cancel
             </Button>
@@ -1,11 +1,56 @@
 import { invoke, Channel } from "@tauri-apps/api/core";
-import type { CourseRecord, CourseManifest, ImportResult, LabData, Route, SlidePosition } from "@/types/browser";
+import type {
+  CatalogEntry,
+  CatalogRefresh,
+  CourseRecord,
+  CourseManifest,
+  CourseUnlocks,
+  Credential,
+  CredentialEntry,
+  DeepLinkEvent,
+  Diagnostic,
+  ImportEvent,
+  ImportResult,
+  LabData,
+  MergeStrategy,
+  Note,
+  NoteAnchor,
+  NoteKind,
+  ProgressImport,
+  ProgressStats,
+  Route,
+  SlidePosition,
+  TrustedPublisher,
+  UpdateCheck,
+  UpdateResult,
+} from "@/types/browser";
 
 type WatchEvent = { readonly event: "changed" };
 type Disposable = { dispose: () => void };
 
-export const courseImport = (sourceUrl: string) =>
-  invoke<ImportResult>("course_import", { sourceUrl });
+export function courseImport(
+  sourceUrl: string,
+  onEvent: (e: ImportEvent) => void = () => {},
+): Promise<ImportResult> {
+  const channel = new Channel<ImportEvent>();
+  channel.onmessage = onEvent;
+  return invoke<ImportResult>("course_import", { sourceUrl, onEvent: channel });
+}
+
+export const courseImportCancel = (sourceUrl: string) =>
+  invoke<void>("course_import_cancel", { sourceUrl });
+
+export const courseImportLocal = (path: string, link: boolean) =>
+  invoke<ImportResult>("course_import_local", { path, link });
+
+export const courseValidate = (path: string) =>
+  invoke<readonly Diagnostic[]>("course_validate", { path });
+
+export const courseImportArchive = (path: string) =>
+  invoke<ImportResult>("course_import_archive", { path });
+
+export const courseExport = (id: string, dest: string) =>
+  invoke<void>("course_export", { id, dest });
 
 export const courseList = () =>
   invoke<readonly CourseRecord[]>("course_list");
@@ -22,31 +67,98 @@ export const courseByTag = (tag: string) =>
 export const courseDelete = (id: string, deleteWorkspaces: boolean) =>
   invoke<void>("course_delete", { id, deleteWorkspaces });
 
+export const courseCheckUpdate = (id: string) =>
+  invoke<UpdateCheck>("course_check_update", { id });
+
+export const courseUpdate = (id: string) =>
+  invoke<UpdateResult>("course_update", { id });
+
+export const catalogRefresh = () =>
+  invoke<readonly CatalogRefresh[]>("catalog_refresh");
+
+export const catalogList = () =>
+  invoke<readonly CatalogEntry[]>("catalog_list");
+
+export const catalogSearch = (query: string) =>
+  invoke<readonly CatalogEntry[]>("catalog_search", { query });
+
+export function catalogInstall(
+  sourceUrl: string,
+  onEvent: (e: ImportEvent) => void = () => {},
+): Promise<ImportResult> {
+  const channel = new Channel<ImportEvent>();
+  channel.onmessage = onEvent;
+  return invoke<ImportResult>("catalog_install", { sourceUrl, onEvent: channel });
+}
+
+export const credentialList = () =>
+  invoke<readonly CredentialEntry[]>("credential_list");
+
+export const credentialSet = (host: string, credential: Credential) =>
+  invoke<void>("credential_set", { host, credential });
+
+export const credentialRemove = (host: string) =>
+  invoke<void>("credential_remove", { host });
+
+export const publisherList = () =>
+  invoke<readonly TrustedPublisher[]>("publisher_list");
+
+export const publisherTrust = (name: string, publicKey: string) =>
+  invoke<void>("publisher_trust", { name, publicKey });
+
+export const publisherUntrust = (name: string) =>
+  invoke<void>("publisher_untrust", { name });
+
 export const stepComplete = (courseId: string, stepIndex: number) =>
   invoke<void>("step_complete", { courseId, stepIndex });
 
 export const stepProgress = (courseId: string) =>
   invoke<readonly number[]>("step_progress", { courseId });
 
+export const courseUnlocks = (id: string) =>
+  invoke<CourseUnlocks>("course_unlocks", { id });
+
 export const routeSave = (route: Route) =>
   invoke<void>("route_save", { route });
 
 export const routeLoad = () =>
   invoke<Route>("route_load");
 
+/** Writes all progress to a JSON file; resolves to the number of courses exported. */
+export const progressExport = (dest: string) =>
+  invoke<number>("progress_export", { dest });
+
+export const progressImport = (path: string, strategy: MergeStrategy) =>
+  invoke<ProgressImport>("progress_import", { path, strategy });
+
+/** Receives handhold:// link outcomes, including ones queued before the call. */
+export function deepLinkSubscribe(onEvent: (event: DeepLinkEvent) => void) {
+  const channel = new Channel<DeepLinkEvent>();
+  channel.onmessage = onEvent;
+  return invoke<void>("deep_link_subscribe", { onEvent: channel });
+}
+
 export const courseGet = (id: string) =>
   invoke<CourseRecord>("course_get", { id });
 
-export const courseManifest = (id: string) =>
-  invoke<CourseManifest>("course_manifest", { id });
+/** Omitting `locale` uses the preferred-locale setting. */
+export const courseManifest = (id: string, locale?: string | null) =>
+  invoke<CourseManifest>("course_manifest", { id, locale });
 
-export const courseReadStep = (id: string, stepPath: string) =>
-  invoke<string>("course_read_step", { id, stepPath });
+export const courseReadStep = (id: string, stepPath: string, locale?: string | null) =>
+  invoke<string>("course_read_step", { id, stepPath, locale });
 
-export const courseReadLab = (id: string, stepPath: string) =>
-  invoke<LabData>("course_read_lab", { id, stepPath });
+export const courseReadLab = (id: string, stepPath: string, locale?: string | null) =>
+  invoke<LabData>("course_read_lab", { id, stepPath, locale });
 
-export type SyncResult = { readonly added: number; readonly removed: number };
+export type SyncResult = {
+  readonly added: number;
+  readonly removed: number;
+  /** Registered courses whose handhold.yaml changed and were refreshed. */
+  readonly updated: readonly { readonly id: string; readonly title: string }[];
+  /** Folders whose manifest couldn't be read or parsed; `id` is null if never registered. */
+  readonly invalid: readonly { readonly id: string | null; readonly path: string; readonly reason: string }[];
+};
 
 export const coursesDirPath = () =>
   invoke<string>("courses_dir_path");
@@ -91,6 +203,45 @@ export const slideComplete = (courseId: string, stepIndex: number, slideId: stri
 export const slideCompletions = (courseId: string, stepIndex: number) =>
   invoke<string[]>("slide_completions", { courseId, stepIndex });
 
+/** Resolves to null when the index doesn't name a step. */
+export const studySessionOpen = (courseId: string, stepIndex: number) =>
+  invoke<number | null>("study_session_open", { courseId, stepIndex });
+
+export const studyHeartbeat = (sessionId: number, activeMs: number) =>
+  invoke<void>("study_heartbeat", { sessionId, activeMs });
+
+export const studySessionClose = (sessionId: number, activeMs: number) =>
+  invoke<void>("study_session_close", { sessionId, activeMs });
+
+export const progressStats = () =>
+  invoke<ProgressStats>("progress_stats");
+
+export const noteCreate = (
+  courseId: string,
+  stepIndex: number,
+  kind: NoteKind,
+  anchor: NoteAnchor,
+  body: string,
+  quote: string | null = null,
+) => invoke<Note>("note_create", { courseId, stepIndex, kind, anchor, body, quote });
+
+export const noteUpdate = (id: number, body: string, quote: string | null = null) =>
+  invoke<Note>("note_update", { id, body, quote });
+
+export const noteDelete = (id: number) =>
+  invoke<void>("note_delete", { id });
+
+export const noteList = (courseId: string, stepIndex: number | null = null) =>
+  invoke<Note[]>("note_list", { courseId, stepIndex });
+
+/** `query` uses FTS5 syntax, like `courseSearch`. */
+export const noteSearch = (query: string, courseId: string | null = null) =>
+  invoke<Note[]>("note_search", { query, courseId });
+
+/** Writes a course's notes to a Markdown file; resolves to how many were written. */
+export const noteExport = (courseId: string, dest: string) =>
+  invoke<number>("note_export", { courseId, dest });
+
 export const checkDependency = (cmd: string) =>
   invoke<boolean>("check_dependency", { cmd });
 
@@ -1,16 +1,18 @@
 import { useQuery, useQueries, useMutation, useQueryClient } from "@tanstack/react-query";
 import * as api from "@/browser/tauri";
-import type { CourseDependency } from "@/types/browser";
+import { useSettingsStore } from "@/lab/settings-store";
+import type { CourseDependency, ImportEvent } from "@/types/browser";
 
 const KEYS = {
   courses: ["courses"] as const,
   course: (id: string) => ["course", id] as const,
-  manifest: (id: string) => ["course-manifest", id] as const,
+  manifest: (id: string, locale: string | null) => ["course-manifest", id, locale] as const,
   step: (id: string, path: string) => ["course-step", id, path] as const,
   tags: ["course-tags"] as const,
   search: (q: string) => ["course-search", q] as const,
   byTag: (t: string) => ["courses-by-tag", t] as const,
   progress: (id: string) => ["step-progress", id] as const,
+  unlocks: (id: string) => ["course-unlocks", id] as const,
   slidePosition: (id: string, step: number) => ["slide-position", id, step] as const,
   slideCompletions: (id: string, step: number) => ["slide-completions", id, step] as const,
   labData: (id: string, path: string) => ["lab-data", id, path] as const,
@@ -24,9 +26,10 @@ export function useCourse(id: string) {
 }
 
 export function useCourseManifest(id: string) {
+  const locale = useSettingsStore((s) => s.preferredLocale);
   return useQuery({
-    queryKey: KEYS.manifest(id),
-    queryFn: () => api.courseManifest(id),
+    queryKey: KEYS.manifest(id, locale),
+    queryFn: () => api.courseManifest(id, locale),
     staleTime: Infinity,
   });
 }
@@ -77,10 +80,17 @@ export function useStepProgress(courseId: string) {
   });
 }
 
-export function useImportCourse() {
+export function useCourseUnlocks(courseId: string) {
+  return useQuery({
+    queryKey: KEYS.unlocks(courseId),
+    queryFn: () => api.courseUnlocks(courseId),
+  });
+}
+
+export function useImportCourse(onEvent?: (e: ImportEvent) => void) {
   const qc = useQueryClient();
   return useMutation({
-    mutationFn: api.courseImport,
+    mutationFn: (sourceUrl: string) => api.courseImport(sourceUrl, onEvent),
     onSuccess: (result) => {
       if (result.kind === "ok") {
         qc.invalidateQueries({ queryKey: KEYS.courses });
@@ -175,6 +185,7 @@ export function useCompleteStep() {
       api.stepComplete(courseId, stepIndex),
     onSuccess: (_, { courseId }) => {
       qc.invalidateQueries({ queryKey: KEYS.progress(courseId) });
+      qc.invalidateQueries({ queryKey: KEYS.unlocks(courseId) });
       qc.invalidateQueries({ queryKey: KEYS.courses });
     },
   });
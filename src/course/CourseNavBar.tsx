@@ -1,7 +1,7 @@
-import { useCallback, useEffect, useRef, useState } from "react";
-import { ArrowLeft, BookOpen, Check, ChevronLeft, ChevronRight, FlaskConical } from "lucide-react";
+import { Fragment, useCallback, useEffect, useRef, useState } from "react";
+import { ArrowLeft, BookOpen, Check, ChevronLeft, ChevronRight, FlaskConical, Lock } from "lucide-react";
 import { Popover, PopoverContent, PopoverTrigger } from "@/components/ui/popover";
-import type { ManifestStep } from "@/types/browser";
+import type { CourseChapter, ManifestStep } from "@/types/browser";
 
 type NavControls = {
   readonly progress: { readonly current: number; readonly total: number };
@@ -11,8 +11,11 @@ type NavControls = {
   readonly prev: () => void;
   readonly stepTitle: string;
   readonly steps: readonly ManifestStep[];
+  readonly chapters: readonly CourseChapter[];
   readonly goTo: (index: number) => void;
   readonly completedSteps: ReadonlySet<number>;
+  /** Steps whose prerequisites aren't met yet. */
+  readonly lockedSteps: ReadonlySet<number>;
 };
 
 type CourseNavBarProps = {
@@ -81,26 +84,45 @@ export function CourseNavBar({ nav, onBack }: CourseNavBarProps) {
               {nav.steps.map((step, i) => {
                 const isCurrent = i === nav.progress.current - 1;
                 const isCompleted = nav.completedSteps.has(i);
+                const isLocked = !isCompleted && nav.lockedSteps.has(i);
+                const chapter = nav.chapters.find((c) => c.firstStep === i);
                 return (
-                  <button
-                    key={step.path}
-                    ref={isCurrent ? currentRef : undefined}
-                    onClick={() => handleSelect(i)}
-                    className={`flex w-full items-center gap-2 px-3 py-1.5 text-left text-xs hover:bg-muted ${isCurrent ? "bg-accent/50 text-accent-foreground" : ""}`}
-                  >
-                    <span className="flex size-4 shrink-0 items-center justify-center text-muted-foreground">
-                      {step.kind === "lesson"
-                        ? <BookOpen className="size-3" />
-                        : <FlaskConical className="size-3" />}
-                    </span>
-                    <span className="tabular-nums text-muted-foreground w-5 shrink-0 text-right">
-                      {i + 1}.
-                    </span>
-                    <span className="truncate">{step.title}</span>
-                    {isCompleted && (
-                      <Check className="ml-auto size-3 shrink-0 text-primary" />
+                  <Fragment key={step.path}>
+                    {chapter !== undefined && (
+                      <div className="px-3 pb-1 pt-2 text-[10px] font-medium uppercase tracking-wide text-muted-foreground">
+                        {chapter.title}
+                      </div>
                     )}
-                  </button>
+                    <button
+                      ref={isCurrent ? currentRef : undefined}
+                      onClick={() => handleSelect(i)}
+                      className={`flex w-full items-center gap-2 px-3 py-1.5 text-left text-xs hover:bg-muted ${isCurrent ? "bg-accent/50 text-accent-foreground" : ""}`}
+                    >
+                      <span className="flex size-4 shrink-0 items-center justify-center text-muted-foreground">
+                        {step.kind === "lesson"
+                          ? <BookOpen className="size-3" />
+                          : <FlaskConical className="size-3" />}
+                      </span>
+                      <span className="tabular-nums text-muted-foreground w-5 shrink-0 text-right">
+                        {i + 1}.
+                      </span>
+                      <span className="truncate">{step.title}</span>
+                      {step.optional && (
+                        <span className="shrink-0 text-muted-foreground">optional</span>
+                      )}
+                      {step.durationMinutes !== null && (
+                        <span className="shrink-0 tabular-nums text-muted-foreground">
+                          {step.durationMinutes}m
+                        </span>
+                      )}
+                      {isCompleted && (
+                        <Check className="ml-auto size-3 shrink-0 text-primary" />
+                      )}
+                      {isLocked && (
+                        <Lock className="ml-auto size-3 shrink-0 text-muted-foreground" />
+                      )}
+                    </button>
+                  </Fragment>
                 );
               })}
             </div>
@@ -0,0 +1,69 @@
+import { useEffect } from "react";
+import { studyHeartbeat, studySessionClose, studySessionOpen } from "@/browser/tauri";
+
+const TICK_MS = 5_000;
+const HEARTBEAT_MS = 30_000;
+// No input for this long means the learner walked away; narration-only
+// slides can run a few minutes, so this is generous.
+const IDLE_MS = 5 * 60_000;
+const INPUT_EVENTS = ["pointerdown", "pointermove", "keydown", "wheel"] as const;
+
+/**
+ * Times the open step for `progress_stats`. The clock only runs while the
+ * window is visible and the learner isn't idle. Pass null until the step is known.
+ */
+export function useStudySession(courseId: string, stepIndex: number | null) {
+  useEffect(() => {
+    if (stepIndex === null) return;
+
+    let lastInput = Date.now();
+    let lastTick = Date.now();
+    let activeMs = 0;
+    let sinceHeartbeat = 0;
+    // Heartbeats and the close run in order, so none lands after the close.
+    let session = studySessionOpen(courseId, stepIndex).catch(() => null);
+
+    const send = (report: (id: number, ms: number) => Promise<void>) => {
+      const ms = activeMs;
+      activeMs = 0;
+      session = session.then(async (id) => {
+        if (id !== null) await report(id, ms).catch(() => {});
+        return id;
+      });
+    };
+
+    const tick = () => {
+      const now = Date.now();
+      // A timer that stalled (sleep, throttled tab) doesn't count the gap.
+      const elapsed = Math.min(now - lastTick, 2 * TICK_MS);
+      if (document.visibilityState === "visible" && now - lastInput < IDLE_MS) {
+        activeMs += elapsed;
+      }
+      sinceHeartbeat += now - lastTick;
+      lastTick = now;
+    };
+
+    const onInput = () => {
+      lastInput = Date.now();
+    };
+    for (const event of INPUT_EVENTS) {
+      window.addEventListener(event, onInput, { passive: true });
+    }
+
+    const timer = setInterval(() => {
+      tick();
+      if (sinceHeartbeat < HEARTBEAT_MS) return;
+      sinceHeartbeat = 0;
+      send(studyHeartbeat);
+    }, TICK_MS);
+
+    return () => {
+      clearInterval(timer);
+      for (const event of INPUT_EVENTS) {
+        window.removeEventListener(event, onInput);
+      }
+      tick();
+      send(studySessionClose);
+    };
+  }, [courseId, stepIndex]);
+}
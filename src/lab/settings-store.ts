@@ -9,6 +9,7 @@ type SettingsActions = {
   setSidebarPanel: (panel: SidebarPanel) => void;
   toggleSidebar: () => void;
   setSuppressCloseConfirm: (suppress: boolean) => void;
+  setPreferredLocale: (locale: string | null) => void;
   hydrate: (settings: AppSettings) => void;
 };
 
@@ -20,6 +21,10 @@ function snapshot(state: SettingsStore): AppSettings {
     sidebarPanel: state.sidebarPanel,
     sidebarCollapsed: state.sidebarCollapsed,
     suppressCloseConfirm: state.suppressCloseConfirm,
+    forges: state.forges,
+    catalogs: state.catalogs,
+    preferredLocale: state.preferredLocale,
+    network: state.network,
   };
 }
 
@@ -47,6 +52,8 @@ export const useSettingsStore = create<SettingsStore>((set, get) => ({
 
   setSuppressCloseConfirm: (suppress) => set({ suppressCloseConfirm: suppress }),
 
+  setPreferredLocale: (locale) => set({ preferredLocale: locale }),
+
   hydrate: (settings) => set(settings),
 }));
 
@@ -6,7 +6,12 @@ export type CourseRecord = {
   readonly title: string;
   readonly description: string;
   readonly stepCount: number;
+  /** Steps not marked optional — the denominator for completion. */
+  readonly requiredSteps: number;
   readonly addedAt: number;
+  /** Commit SHA the installed content came from; null for local and plain-HTTP courses. */
+  readonly revision: string | null;
+  /** Completed required steps; optional ones don't count. */
   readonly completedSteps: number;
   readonly tags: readonly string[];
 };
@@ -19,18 +24,170 @@ export type ImportResult =
   | { readonly kind: "noManifest" }
   | { readonly kind: "badManifest"; readonly reason: string }
   | { readonly kind: "alreadyExists" }
-  | { readonly kind: "downloadFailed"; readonly reason: string };
+  | { readonly kind: "downloadFailed"; readonly reason: string }
+  | { readonly kind: "badPackage"; readonly reason: string }
+  | { readonly kind: "integrityFailed"; readonly file: string }
+  | { readonly kind: "untrustedPublisher"; readonly publisher: string }
+  | { readonly kind: "cancelled" }
+  | { readonly kind: "unauthorized"; readonly host: string }
+  | { readonly kind: "invalid"; readonly diagnostics: readonly Diagnostic[] }
+  | { readonly kind: "needsNewerApp"; readonly required: number; readonly supported: number }
+  | { readonly kind: "networkError"; readonly reason: string }
+  | { readonly kind: "unpinned"; readonly reason: string };
+
+/** One finding from `course_validate`. `file` is relative to the course root. */
+export type Diagnostic = {
+  readonly file: string;
+  /** 1-based, when the finding can be pinned to a line. */
+  readonly line: number | null;
+  readonly severity: "error" | "warning";
+  readonly message: string;
+};
+
+/** Import phases streamed while `course_import` runs. */
+export type ImportEvent =
+  | { readonly event: "resolving" }
+  | { readonly event: "manifestFetched"; readonly title: string; readonly stepCount: number }
+  | { readonly event: "downloaded"; readonly bytes: number; readonly total: number | null }
+  | { readonly event: "extracted"; readonly files: number }
+  | { readonly event: "registering" };
+
+/** What an update would change, relative to the installed copy. Steps are matched by path. */
+export type CourseChanges = {
+  /** The source moved to a new revision; step files may differ even if nothing below does. */
+  readonly contentChanged: boolean;
+  readonly titleChanged: boolean;
+  readonly descriptionChanged: boolean;
+  readonly stepsAdded: readonly string[];
+  readonly stepsRemoved: readonly string[];
+  readonly stepsEdited: readonly string[];
+  readonly tagsAdded: readonly string[];
+  readonly tagsRemoved: readonly string[];
+};
+
+export type UpdateCheck =
+  | { readonly kind: "upToDate" }
+  | { readonly kind: "available"; readonly changes: CourseChanges }
+  | { readonly kind: "unsupported" }
+  | { readonly kind: "notFound" }
+  | { readonly kind: "noManifest" }
+  | { readonly kind: "badManifest"; readonly reason: string }
+  | { readonly kind: "unauthorized"; readonly host: string }
+  | { readonly kind: "needsNewerApp"; readonly required: number; readonly supported: number }
+  | { readonly kind: "networkError"; readonly reason: string }
+  | { readonly kind: "unpinned"; readonly reason: string };
+
+export type UpdateResult =
+  | { readonly kind: "ok"; readonly course: CourseRecord; readonly changes: CourseChanges }
+  | { readonly kind: "unsupported" }
+  | { readonly kind: "notFound" }
+  | { readonly kind: "noManifest" }
+  | { readonly kind: "badManifest"; readonly reason: string }
+  | { readonly kind: "downloadFailed"; readonly reason: string }
+  | { readonly kind: "integrityFailed"; readonly file: string }
+  | { readonly kind: "untrustedPublisher"; readonly publisher: string }
+  | { readonly kind: "unauthorized"; readonly host: string }
+  | { readonly kind: "invalid"; readonly diagnostics: readonly Diagnostic[] }
+  | { readonly kind: "needsNewerApp"; readonly required: number; readonly supported: number }
+  | { readonly kind: "networkError"; readonly reason: string }
+  | { readonly kind: "unpinned"; readonly reason: string };
+
+/** A publisher key in ~/.handhold/trusted-publishers.json. */
+export type TrustedPublisher = {
+  readonly name: string;
+  /** Base64 ed25519 public key. */
+  readonly publicKey: string;
+  readonly addedAt: number;
+};
+
+/** A course advertised by a catalog index, cached for offline browsing. */
+export type CatalogEntry = {
+  readonly sourceUrl: string;
+  readonly catalog: string;
+  readonly title: string;
+  readonly description: string;
+  readonly tags: readonly string[];
+  readonly revision: string | null;
+  readonly installed: boolean;
+};
+
+export type CatalogRefresh =
+  | { readonly kind: "ok"; readonly catalog: string; readonly courses: number; readonly skipped: number }
+  | { readonly kind: "failed"; readonly catalog: string; readonly reason: string };
+
+/** Per-host secret for private repos and servers. Stored outside settings.json. */
+export type Credential =
+  | { readonly kind: "bearer"; readonly token: string }
+  | { readonly kind: "basic"; readonly username: string; readonly password: string };
+
+export type CredentialEntry = {
+  readonly host: string;
+  readonly kind: Credential["kind"];
+};
 
 /** App-level navigation state persisted to SQLite. */
 export type Route =
   | { readonly kind: "browser" }
   | { readonly kind: "course"; readonly courseId: string; readonly stepIndex: number };
 
+/** How `progress_import` combines a file with existing progress. */
+export type MergeStrategy = "union" | "overwrite";
+
+export type ProgressImport =
+  | {
+      readonly kind: "ok";
+      readonly courses: number;
+      readonly unmatchedCourses: readonly { readonly sourceUrl: string; readonly title: string }[];
+      readonly unmatchedSteps: readonly { readonly sourceUrl: string; readonly stepId: string }[];
+    }
+  | { readonly kind: "badFile"; readonly reason: string };
+
+/** Outcome of a handhold:// link, streamed by `deep_link_subscribe`. */
+export type DeepLinkEvent =
+  | { readonly kind: "navigate"; readonly route: Route }
+  /** Open the import dialog prefilled; nothing is downloaded until the user confirms. */
+  | { readonly kind: "importRequested"; readonly sourceUrl: string }
+  | { readonly kind: "rejected"; readonly url: string; readonly reason: string };
+
 /** A step entry from handhold.yaml — the manifest on disk. */
 export type ManifestStep = {
+  /** Stable progress key; defaults to a hash of `path`. */
+  readonly id: string;
   readonly kind: "lesson" | "lab";
   readonly title: string;
   readonly path: string;
+  /** Skippable; doesn't count toward course completion. */
+  readonly optional: boolean;
+  readonly durationMinutes: number | null;
+  readonly objectives: readonly string[];
+  /** Steps to complete first, by id or path. */
+  readonly requires: readonly string[];
+};
+
+/** Why a step can't be completed yet. */
+export type LockReason =
+  | { readonly kind: "step"; readonly stepIndex: number; readonly title: string }
+  | { readonly kind: "course"; readonly sourceUrl: string; readonly title: string }
+  | { readonly kind: "courseMissing"; readonly sourceUrl: string };
+
+export type StepLock = {
+  readonly stepIndex: number;
+  readonly reasons: readonly LockReason[];
+};
+
+/** Gating state for one course. `course` reasons lock every step. */
+export type CourseUnlocks = {
+  readonly freeNavigation: boolean;
+  readonly course: readonly LockReason[];
+  readonly steps: readonly StepLock[];
+};
+
+/** A titled run of consecutive steps, starting at steps[firstStep]. */
+export type CourseChapter = {
+  readonly title: string;
+  readonly description: string;
+  readonly firstStep: number;
+  readonly stepCount: number;
 };
 
 /** Tracks which slide a user is on within a lesson. */
@@ -39,6 +196,77 @@ export type SlidePosition = {
   readonly slideCount: number | null;
 };
 
+export type NoteKind = "note" | "bookmark" | "highlight";
+
+/** Where in its step a note is attached. Lab file lines count from 1, inclusive. */
+export type NoteAnchor =
+  | { readonly kind: "step" }
+  | { readonly kind: "slide"; readonly slideId: string }
+  | {
+      readonly kind: "labFile";
+      readonly path: string;
+      readonly lineStart: number | null;
+      readonly lineEnd: number | null;
+    };
+
+/** A learner-written note, bookmark or highlight. */
+export type Note = {
+  readonly id: number;
+  readonly courseId: string;
+  readonly stepId: string;
+  /** null once the step has been removed from the course. */
+  readonly stepIndex: number | null;
+  readonly kind: NoteKind;
+  readonly anchor: NoteAnchor;
+  /** The highlighted text, for highlights. */
+  readonly quote: string | null;
+  readonly body: string;
+  readonly createdAt: number;
+  readonly updatedAt: number;
+};
+
+/** Learning time and completion history, from `progress_stats`. */
+export type ProgressStats = {
+  /** Courses with any tracked time, most time first. */
+  readonly courses: readonly CourseTime[];
+  readonly streak: Streak;
+  /** Oldest first; weeks without completions are omitted. */
+  readonly weeklyCompletions: readonly WeekCompletions[];
+  /** Mean active time across completed labs; null before the first one. */
+  readonly averageLabMs: number | null;
+  readonly completedLabs: number;
+};
+
+export type CourseTime = {
+  readonly courseId: string;
+  readonly title: string;
+  readonly activeMs: number;
+  readonly steps: readonly StepTime[];
+};
+
+export type StepTime = {
+  readonly stepId: string;
+  /** null once the step has been removed from the course. */
+  readonly stepIndex: number | null;
+  readonly kind: "lesson" | "lab";
+  readonly activeMs: number;
+  readonly sessions: number;
+};
+
+/** Consecutive local days with study time or a completion. */
+export type Streak = {
+  readonly currentDays: number;
+  readonly longestDays: number;
+  /** YYYY-MM-DD, local time. */
+  readonly lastActiveDay: string | null;
+};
+
+export type WeekCompletions = {
+  /** Monday the week starts on, YYYY-MM-DD. */
+  readonly weekStart: string;
+  readonly completions: number;
+};
+
 /** A course dependency entry — install command already resolved for the current OS. */
 export type CourseDependency = {
   readonly name: string;
@@ -54,6 +282,11 @@ export type CourseManifest = {
   readonly description: string;
   readonly tags: readonly string[];
   readonly steps: readonly ManifestStep[];
+  /** Empty when the course isn't split into chapters. */
+  readonly chapters: readonly CourseChapter[];
+  /** Locale the text and step paths were resolved to; null is the base. */
+  readonly locale: string | null;
+  readonly locales: readonly string[];
   readonly dependencies: readonly CourseDependency[];
 };
 
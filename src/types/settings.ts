@@ -18,11 +18,41 @@ export type EditorSettings = {
 
 export type SidebarPanel = "explorer" | "instructions" | "search" | "services" | "testing" | "settings" | "solution";
 
+/** Self-hosted forge the course importer should recognise by hostname. `bitbucket` only matches bitbucket.org. */
+export type ForgeHost = {
+  readonly host: string;
+  readonly kind: "gitlab" | "gitea" | "bitbucket";
+};
+
+/** Requests for URLs starting with `from` go to `to` plus the rest of the URL. */
+export type MirrorRule = {
+  readonly from: string;
+  readonly to: string;
+};
+
+/** Outbound HTTP for imports, updates and catalogs. Unset fields fall back to the environment. */
+export type NetworkSettings = {
+  readonly proxy: string | null;
+  readonly noProxy: string | null;
+  /** PEM files trusted on top of the bundled roots. */
+  readonly caCertificates: readonly string[];
+  readonly connectTimeoutSecs: number;
+  readonly readTimeoutSecs: number;
+  readonly retries: number;
+  readonly mirrors: readonly MirrorRule[];
+};
+
 export type AppSettings = {
   readonly editor: EditorSettings;
   readonly sidebarPanel: SidebarPanel;
   readonly sidebarCollapsed: boolean;
   readonly suppressCloseConfirm: boolean;
+  readonly forges: readonly ForgeHost[];
+  /** Course catalog indexes: http(s) URLs or local file paths. */
+  readonly catalogs: readonly string[];
+  /** BCP 47 tag such as "es" or "ja-JP"; null shows each course's base text. */
+  readonly preferredLocale: string | null;
+  readonly network: NetworkSettings;
 };
 
 export const DEFAULT_EDITOR: EditorSettings = {
@@ -39,9 +69,23 @@ export const DEFAULT_EDITOR: EditorSettings = {
   autoSaveDelay: 1000,
 } as const;
 
+export const DEFAULT_NETWORK: NetworkSettings = {
+  proxy: null,
+  noProxy: null,
+  caCertificates: [],
+  connectTimeoutSecs: 15,
+  readTimeoutSecs: 60,
+  retries: 2,
+  mirrors: [],
+} as const;
+
 export const DEFAULT_SETTINGS: AppSettings = {
   editor: DEFAULT_EDITOR,
   sidebarPanel: "explorer",
   sidebarCollapsed: false,
   suppressCloseConfirm: false,
+  forges: [],
+  catalogs: [],
+  preferredLocale: null,
+  network: DEFAULT_NETWORK,
 } as const;